@@ -1,35 +1,87 @@
 use super::commands::Commands;
 use super::host::{HostRef, DEFAULT_HOST_REF};
 use super::refs::{flatten_optional_refs, ActionRefs};
-use crate::utils::Result;
-use serde::Deserialize;
+use super::response::ResponseMode;
+use super::trigger::Trigger;
+use crate::utils::{Error, Location, Result};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
 
-#[derive(Deserialize, Debug, PartialEq)]
+/// What is written in place of the secrets when an action is serialized.
+pub const REDACTED_SECRET: &str = "********";
+
+/// An action as it is written in a group file.
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
 #[serde(deny_unknown_fields, expecting = "an action")]
 pub struct ActionEntry {
     name: String,
     #[serde(rename = "on")]
-    trigger: String,
-    #[serde(rename = "from", default)]
+    trigger: Trigger,
+    #[serde(default, skip_serializing_if = "is_false")]
+    shared_trigger: bool,
+    #[serde(rename = "from", default, skip_serializing_if = "Option::is_none")]
     origin: Option<HostRef>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     secret: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     before: Option<ActionRefs>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     after: Option<ActionRefs>,
-    #[serde(rename = "run")]
+    #[serde(default, skip_serializing_if = "ResponseMode::is_immediate")]
+    respond: ResponseMode,
+    #[serde(rename = "run", serialize_with = "Commands::serialize_as_text")]
     action: Commands,
 }
 
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl ActionEntry {
+    pub fn new(name: String, trigger: Trigger, commands: Commands) -> Self {
+        Self {
+            name,
+            trigger,
+            shared_trigger: false,
+            origin: None,
+            secret: None,
+            before: None,
+            after: None,
+            respond: ResponseMode::Immediate,
+            action: commands,
+        }
+    }
+
+    pub fn with_origin(self, origin: HostRef) -> Self {
+        Self {
+            origin: Some(origin),
+            ..self
+        }
+    }
+
+    pub fn with_secret(self, secret: String) -> Self {
+        Self {
+            secret: Some(secret),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 #[derive(Debug)]
 pub struct Action {
-    trigger: String,
+    trigger: Trigger,
+    shared_trigger: bool,
     origin: Option<HostRef>,
     secret: Option<String>,
     before: Vec<String>,
     after: Vec<String>,
+    respond: ResponseMode,
     action: Commands,
+    location: Option<Location>,
 }
 
 impl Action {
@@ -38,25 +90,33 @@ impl Action {
             deserialized_action.name,
             Self {
                 trigger: deserialized_action.trigger,
+                shared_trigger: deserialized_action.shared_trigger,
                 origin: deserialized_action.origin,
                 secret: deserialized_action.secret,
                 before: flatten_optional_refs(deserialized_action.before),
                 after: flatten_optional_refs(deserialized_action.after),
+                respond: deserialized_action.respond,
                 action: deserialized_action.action,
+                location: None,
             },
         ))
     }
 
-    pub fn trigger(&self) -> &str {
+    pub fn trigger(&self) -> &Trigger {
         &self.trigger
     }
 
+    /// Whether other actions are allowed to use the same trigger.
+    pub fn shares_trigger(&self) -> bool {
+        self.shared_trigger
+    }
+
     pub fn origin(&self) -> &HostRef {
         self.origin.as_ref().unwrap_or(&DEFAULT_HOST_REF)
     }
 
     pub fn secret(&self) -> Option<&str> {
-        self.secret.as_ref().map(|x| x.as_str())
+        self.secret.as_deref()
     }
 
     pub fn before(&self) -> &Vec<String> {
@@ -67,9 +127,51 @@ impl Action {
         &self.after
     }
 
+    pub fn response_mode(&self) -> &ResponseMode {
+        &self.respond
+    }
+
     pub fn action(&self) -> &Commands {
         &self.action
     }
+
+    /// Where this action is declared, if it could be found in the group file.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    pub(crate) fn set_location(&mut self, location: Location) {
+        self.location = Some(location);
+    }
+
+    /// Adds the location of this action to an error, if it is known.
+    pub(crate) fn locate(&self, error: Error) -> Error {
+        match &self.location {
+            Some(location) => error.at(location.clone()),
+            None => error,
+        }
+    }
+
+    /// Iterates over the references found in the `before` and `after` fields.
+    pub fn refs(&self) -> impl Iterator<Item = &String> {
+        self.before.iter().chain(self.after.iter())
+    }
+}
+
+impl Serialize for Action {
+    /// Serializes the action with the same keys as in the group files, without its secret.
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Action", 8)?;
+        state.serialize_field("on", &self.trigger)?;
+        state.serialize_field("shared_trigger", &self.shared_trigger)?;
+        state.serialize_field("from", self.origin())?;
+        state.serialize_field("secret", &self.secret.as_ref().map(|_| REDACTED_SECRET))?;
+        state.serialize_field("before", &self.before)?;
+        state.serialize_field("after", &self.after)?;
+        state.serialize_field("respond", &self.respond)?;
+        state.serialize_field("run", &self.action)?;
+        state.end()
+    }
 }
 
 #[cfg(test)]
@@ -79,14 +181,44 @@ pub mod fakes {
     pub fn make_test_action_entry(name: &'_ str) -> ActionEntry {
         ActionEntry {
             name: name.to_owned(),
-            trigger: String::from("POST /test/action"),
+            trigger: Trigger::parse("POST /test/action").unwrap(),
+            shared_trigger: false,
             origin: None,
             secret: None,
             before: None,
             after: None,
+            respond: ResponseMode::Immediate,
             action: Commands::new(vec![String::from("some-command")]),
         }
     }
+
+    pub fn make_test_action_with_trigger(trigger: &str) -> Action {
+        Action {
+            trigger: Trigger::parse(trigger).unwrap(),
+            ..make_test_action(&[], &[])
+        }
+    }
+
+    pub fn make_test_action_with_shared_trigger(trigger: &str) -> Action {
+        Action {
+            shared_trigger: true,
+            ..make_test_action_with_trigger(trigger)
+        }
+    }
+
+    pub fn make_test_action(before: &[&str], after: &[&str]) -> Action {
+        Action {
+            trigger: Trigger::parse("POST /test/action").unwrap(),
+            shared_trigger: false,
+            origin: None,
+            secret: None,
+            before: before.iter().map(|r| String::from(*r)).collect(),
+            after: after.iter().map(|r| String::from(*r)).collect(),
+            respond: ResponseMode::Immediate,
+            action: Commands::new(vec![String::from("some-command")]),
+            location: None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -98,7 +230,8 @@ mod tests {
     fn yaml_all() {
         let expected = ActionEntry {
             name: String::from("my-action"),
-            trigger: String::from("POST /my/action"),
+            trigger: Trigger::parse("POST /my/action").unwrap(),
+            shared_trigger: true,
             origin: Some(HostRef::Any),
             secret: Some(String::from("my_secret_key")),
             before: Some(ActionRefs::Single(String::from("other-action"))),
@@ -106,31 +239,62 @@ mod tests {
                 String::from("something-else"),
                 String::from("other-group/action"),
             ])),
+            respond: ResponseMode::Wait(Default::default()),
             action: Commands::new(vec![String::from("some-command")]),
         };
 
         let parsed = serde_yaml::from_str::<ActionEntry>(indoc! {"
             name: my-action
             on: POST /my/action
+            shared_trigger: true
             from: '*'
             secret: my_secret_key
             before: other-action
             after: [ something-else, other-group/action ]
+            respond: wait
             run: some-command
         "});
 
         assert_eq!(expected, parsed.unwrap());
     }
 
+    #[test]
+    fn serialize_without_secret() {
+        let action = Action {
+            secret: Some(String::from("my_secret_key")),
+            ..fakes::make_test_action(&["build"], &[])
+        };
+
+        let serialized = serde_yaml::to_string(&action).unwrap();
+
+        assert_eq!(
+            serialized,
+            indoc! {"
+                on: POST /test/action
+                shared_trigger: false
+                from: '*'
+                secret: '********'
+                before:
+                - build
+                after: []
+                respond: immediate
+                run:
+                - some-command
+            "}
+        );
+    }
+
     #[test]
     fn yaml_minimal() {
         let expected = ActionEntry {
             name: String::from("my-action"),
-            trigger: String::from("POST /my/action"),
+            trigger: Trigger::parse("POST /my/action").unwrap(),
+            shared_trigger: false,
             origin: None,
             secret: None,
             before: None,
             after: None,
+            respond: ResponseMode::Immediate,
             action: Commands::new(vec![String::from("some-command")]),
         };
 
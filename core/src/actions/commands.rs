@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::fmt::{Display, Formatter};
 
 #[derive(Deserialize, Debug, PartialEq, Clone)]
@@ -15,6 +15,14 @@ impl Commands {
     pub fn has_multiple_commands(&self) -> bool {
         self.commands.len() > 1
     }
+
+    /// Serializes the commands as they are written in the group files, one per line.
+    pub(crate) fn serialize_as_text<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.commands.join("\n"))
+    }
 }
 
 impl From<String> for Commands {
@@ -34,6 +42,12 @@ impl From<String> for Commands {
     }
 }
 
+impl Serialize for Commands {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.commands)
+    }
+}
+
 impl Display for Commands {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         for cmd in &self.commands {
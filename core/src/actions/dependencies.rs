@@ -0,0 +1,477 @@
+use super::{Action, ExecutionPlan, Group};
+use crate::problem;
+use crate::utils::{ActionPath, Report, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The references between all the known actions.
+pub struct Dependencies<'a> {
+    actions: BTreeMap<ActionPath, &'a Action>,
+}
+
+/// A reference found in the `before` or `after` field of an action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub from: ActionPath,
+    pub kind: ReferenceKind,
+    /// The referenced action, or the reference as it is written if it isn't a valid path.
+    pub to: String,
+    /// False if the referenced action doesn't exist.
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// The referenced action runs before the action.
+    Before,
+    /// The referenced action runs after the action.
+    After,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisitState {
+    InProgress,
+    Done,
+}
+
+impl<'a> Dependencies<'a> {
+    pub fn from_groups<I>(groups: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a Group)>,
+    {
+        groups
+            .into_iter()
+            .flat_map(|(group_name, group)| {
+                group.enumerate_actions().map(move |(action_name, action)| {
+                    (ActionPath::from_parts(group_name, action_name), action)
+                })
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, path: &ActionPath) -> bool {
+        self.actions.contains_key(path)
+    }
+
+    /// The known actions, sorted by path.
+    pub fn paths(&self) -> impl Iterator<Item = &ActionPath> {
+        self.actions.keys()
+    }
+
+    pub fn get(&self, path: &ActionPath) -> Option<&'a Action> {
+        self.actions.get(path).copied()
+    }
+
+    /// The references of an action, in the order they are written (`before` first).
+    pub fn references_of(&self, path: &ActionPath) -> Vec<Reference> {
+        let Some(action) = self.actions.get(path) else {
+            return Vec::new();
+        };
+        let kinds = action
+            .before()
+            .iter()
+            .map(|reference| (ReferenceKind::Before, reference))
+            .chain(
+                action
+                    .after()
+                    .iter()
+                    .map(|reference| (ReferenceKind::After, reference)),
+            );
+        kinds
+            .map(|(kind, reference)| match path.resolve(reference) {
+                Ok(resolved) => Reference {
+                    from: path.clone(),
+                    kind,
+                    exists: self.actions.contains_key(&resolved),
+                    to: resolved.to_string(),
+                },
+                Err(_) => Reference {
+                    from: path.clone(),
+                    kind,
+                    to: reference.clone(),
+                    exists: false,
+                },
+            })
+            .collect()
+    }
+
+    /// Checks that every reference points to an existing action and that there are no cycles.
+    pub fn check(&self) -> Result<()> {
+        let mut report = Report::new();
+
+        for (path, action) in &self.actions {
+            for reference in action.refs() {
+                if let Err(error) = self.resolve(path, reference) {
+                    report.add(
+                        action.locate(
+                            problem!("Invalid reference in action '{}'", path).because(error),
+                        ),
+                    );
+                }
+            }
+        }
+
+        let ordering = self.ordering();
+        let mut states = BTreeMap::new();
+        let mut stack = Vec::new();
+        for path in self.actions.keys() {
+            self.visit(path, &ordering, &mut states, &mut stack, &mut report);
+        }
+
+        report.wrap(())
+    }
+
+    /// Checks the references and computes the execution plan of every action.
+    pub fn plans(&self) -> Result<BTreeMap<ActionPath, ExecutionPlan>> {
+        self.check()?;
+
+        let mut plans = BTreeMap::new();
+        let mut report = Report::new();
+        for (path, action) in &self.actions {
+            match self.plan(path) {
+                Ok(plan) => {
+                    plans.insert(path.clone(), plan);
+                }
+                Err(error) => report.add(action.locate(error)),
+            }
+        }
+
+        report.wrap(plans)
+    }
+
+    /// Orders the actions that run when `target` is triggered. Each action runs once, after the
+    /// actions listed in its `before` field and before the actions listed in its `after` field.
+    pub fn plan(&self, target: &ActionPath) -> Result<ExecutionPlan> {
+        if !self.actions.contains_key(target) {
+            return Err(problem!("action '{}' not found", target));
+        }
+
+        // the order in which the actions are first encountered is used to break ties
+        let mut preferred = Vec::new();
+        self.collect(target, &mut BTreeSet::new(), &mut preferred)?;
+        let rank: BTreeMap<&ActionPath, usize> = preferred
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (path, index))
+            .collect();
+
+        let mut successors = vec![Vec::new(); preferred.len()];
+        let mut predecessor_count = vec![0usize; preferred.len()];
+        for (index, path) in preferred.iter().enumerate() {
+            let action = self.actions[path];
+            for reference in action.before() {
+                let other = rank[&path.resolve(reference)?];
+                successors[other].push(index);
+                predecessor_count[index] += 1;
+            }
+            for reference in action.after() {
+                let other = rank[&path.resolve(reference)?];
+                successors[index].push(other);
+                predecessor_count[other] += 1;
+            }
+        }
+
+        let mut ready: BTreeSet<usize> = (0..preferred.len())
+            .filter(|index| predecessor_count[*index] == 0)
+            .collect();
+        let mut steps = Vec::with_capacity(preferred.len());
+        while let Some(index) = ready.pop_first() {
+            steps.push(preferred[index].clone());
+            for next in &successors[index] {
+                predecessor_count[*next] -= 1;
+                if predecessor_count[*next] == 0 {
+                    ready.insert(*next);
+                }
+            }
+        }
+
+        if steps.len() < preferred.len() {
+            let conflicting: Vec<String> = (0..preferred.len())
+                .filter(|index| predecessor_count[*index] > 0)
+                .map(|index| preferred[index].to_string())
+                .collect();
+            Err(problem!(
+                "Conflicting order between {} when running '{}'",
+                conflicting.join(", "),
+                target
+            ))
+        } else {
+            Ok(ExecutionPlan::new(steps))
+        }
+    }
+
+    fn collect(
+        &self,
+        path: &ActionPath,
+        seen: &mut BTreeSet<ActionPath>,
+        preferred: &mut Vec<ActionPath>,
+    ) -> Result<()> {
+        if !seen.insert(path.clone()) {
+            return Ok(());
+        }
+
+        let action = self.actions[path];
+        for reference in action.before() {
+            self.collect(&self.resolve(path, reference)?, seen, preferred)?;
+        }
+        preferred.push(path.clone());
+        for reference in action.after() {
+            self.collect(&self.resolve(path, reference)?, seen, preferred)?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, from: &ActionPath, reference: &str) -> Result<ActionPath> {
+        let path = from.resolve(reference)?;
+        if self.actions.contains_key(&path) {
+            Ok(path)
+        } else {
+            Err(problem!("action '{}' not found", path))
+        }
+    }
+
+    /// Maps each action to the actions that must run after it. An action listed in the `before`
+    /// field of another one and listing it in its `after` field give the same order once.
+    fn ordering(&self) -> BTreeMap<&ActionPath, BTreeSet<ActionPath>> {
+        let mut ordering: BTreeMap<&ActionPath, BTreeSet<ActionPath>> = self
+            .actions
+            .keys()
+            .map(|path| (path, BTreeSet::new()))
+            .collect();
+        for (path, action) in &self.actions {
+            for reference in action.before() {
+                if let Ok(previous) = self.resolve(path, reference) {
+                    if let Some(next) = ordering.get_mut(&previous) {
+                        next.insert(path.clone());
+                    }
+                }
+            }
+            for reference in action.after() {
+                if let Ok(next) = self.resolve(path, reference) {
+                    if let Some(following) = ordering.get_mut(path) {
+                        following.insert(next);
+                    }
+                }
+            }
+        }
+        ordering
+    }
+
+    fn visit(
+        &self,
+        path: &ActionPath,
+        ordering: &BTreeMap<&ActionPath, BTreeSet<ActionPath>>,
+        states: &mut BTreeMap<ActionPath, VisitState>,
+        stack: &mut Vec<ActionPath>,
+        report: &mut Report,
+    ) {
+        match states.get(path) {
+            Some(VisitState::Done) => return,
+            Some(VisitState::InProgress) => {
+                let start = stack.iter().position(|p| p == path).unwrap_or(0);
+                let cycle: Vec<String> = stack[start..]
+                    .iter()
+                    .chain([path])
+                    .map(ToString::to_string)
+                    .collect();
+                report.add(
+                    self.actions[path].locate(problem!("Dependency cycle: {}", cycle.join(" -> "))),
+                );
+                return;
+            }
+            None => {}
+        }
+
+        states.insert(path.clone(), VisitState::InProgress);
+        stack.push(path.clone());
+        for next in ordering.get(path).into_iter().flatten() {
+            self.visit(next, ordering, states, stack, report);
+        }
+        stack.pop();
+        states.insert(path.clone(), VisitState::Done);
+    }
+}
+
+impl<'a> FromIterator<(ActionPath, &'a Action)> for Dependencies<'a> {
+    fn from_iter<T: IntoIterator<Item = (ActionPath, &'a Action)>>(iter: T) -> Self {
+        Self {
+            actions: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::action::fakes::*;
+
+    fn dependencies<'a>(actions: &'a [(&str, Action)]) -> Dependencies<'a> {
+        actions
+            .iter()
+            .map(|(path, action)| (ActionPath::parse(path).unwrap(), action))
+            .collect()
+    }
+
+    fn check(actions: &[(&str, Action)]) -> Result<()> {
+        dependencies(actions).check()
+    }
+
+    fn plan(actions: &[(&str, Action)], target: &str) -> Result<String> {
+        dependencies(actions)
+            .plan(&ActionPath::parse(target).unwrap())
+            .map(|plan| plan.to_string())
+    }
+
+    #[test]
+    fn valid_references() {
+        let result = check(&[
+            ("g/a", make_test_action(&["g/b"], &["h/c"])),
+            ("g/b", make_test_action(&[], &["h/c"])),
+            ("h/c", make_test_action(&[], &[])),
+        ]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn relative_references() {
+        let result = check(&[
+            ("g/a", make_test_action(&["b"], &["../h/c"])),
+            ("g/b", make_test_action(&["./a"], &[])),
+            ("h/c", make_test_action(&[], &[])),
+        ]);
+
+        assert!(result.is_err());
+        assert!(format!("{:?}", result.unwrap_err()).contains("g/a -> g/b -> g/a"));
+    }
+
+    #[test]
+    fn unknown_reference() {
+        let result = check(&[("g/a", make_test_action(&["g/b"], &[]))]);
+
+        assert!(result.is_err());
+        assert!(format!("{:?}", result.unwrap_err()).contains("action 'g/b' not found"));
+    }
+
+    #[test]
+    fn self_reference() {
+        let result = check(&[("g/a", make_test_action(&[], &["g/a"]))]);
+
+        assert!(result.is_err());
+        assert!(format!("{:?}", result.unwrap_err()).contains("g/a -> g/a"));
+    }
+
+    #[test]
+    fn cycle() {
+        let result = check(&[
+            ("g/a", make_test_action(&[], &["g/b"])),
+            ("g/b", make_test_action(&[], &["h/c"])),
+            ("h/c", make_test_action(&[], &["g/a"])),
+        ]);
+
+        assert!(result.is_err());
+        assert!(format!("{:?}", result.unwrap_err()).contains("g/a -> g/b -> h/c -> g/a"));
+    }
+
+    #[test]
+    fn same_order_from_both_sides() {
+        let actions = [
+            ("g/a", make_test_action(&["g/b"], &[])),
+            ("g/b", make_test_action(&[], &["g/a"])),
+        ];
+
+        assert!(check(&actions).is_ok());
+        assert_eq!(plan(&actions, "g/a").unwrap(), "g/b -> g/a");
+        assert_eq!(plan(&actions, "g/b").unwrap(), "g/b -> g/a");
+    }
+
+    #[test]
+    fn references_in_both_directions_without_cycle() {
+        // b runs before a and c, and a before c
+        let result = check(&[
+            ("g/a", make_test_action(&["g/b"], &[])),
+            ("g/b", make_test_action(&[], &["h/c"])),
+            ("h/c", make_test_action(&["g/a"], &[])),
+        ]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn list_references() {
+        let actions = [
+            ("g/a", make_test_action(&["b", "h/missing"], &["../h/c"])),
+            ("g/b", make_test_action(&[], &[])),
+            ("h/c", make_test_action(&[], &[])),
+        ];
+        let dependencies = dependencies(&actions);
+
+        let references = dependencies.references_of(&ActionPath::parse("g/a").unwrap());
+
+        let summary: Vec<(ReferenceKind, &str, bool)> = references
+            .iter()
+            .map(|reference| (reference.kind, reference.to.as_str(), reference.exists))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (ReferenceKind::Before, "g/b", true),
+                (ReferenceKind::Before, "h/missing", false),
+                (ReferenceKind::After, "h/c", true),
+            ]
+        );
+        assert!(dependencies
+            .references_of(&ActionPath::parse("g/b").unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn plan_chain() {
+        let actions = [
+            ("g/a", make_test_action(&["g/b"], &["g/c"])),
+            ("g/b", make_test_action(&["g/d"], &[])),
+            ("g/c", make_test_action(&[], &["g/e"])),
+            ("g/d", make_test_action(&[], &[])),
+            ("g/e", make_test_action(&[], &[])),
+        ];
+
+        assert_eq!(plan(&actions, "g/a").unwrap(), "g/d -> g/b -> g/a -> g/c -> g/e");
+    }
+
+    #[test]
+    fn plan_diamond() {
+        let actions = [
+            ("g/deploy", make_test_action(&["g/test", "g/lint"], &[])),
+            ("g/test", make_test_action(&["g/build"], &[])),
+            ("g/lint", make_test_action(&["g/build"], &[])),
+            ("g/build", make_test_action(&[], &[])),
+        ];
+
+        assert_eq!(
+            plan(&actions, "g/deploy").unwrap(),
+            "g/build -> g/test -> g/lint -> g/deploy"
+        );
+    }
+
+    #[test]
+    fn plan_respects_after_constraints() {
+        let actions = [
+            ("g/a", make_test_action(&["g/b", "g/c"], &[])),
+            ("g/b", make_test_action(&[], &[])),
+            ("g/c", make_test_action(&[], &["g/b"])),
+        ];
+
+        assert_eq!(plan(&actions, "g/a").unwrap(), "g/c -> g/b -> g/a");
+    }
+
+    #[test]
+    fn plan_conflicting_order() {
+        let actions = [
+            ("g/a", make_test_action(&["g/b"], &["g/b"])),
+            ("g/b", make_test_action(&[], &[])),
+        ];
+
+        assert!(plan(&actions, "g/a").is_err());
+        assert!(dependencies(&actions).plans().is_err());
+    }
+}
@@ -1,9 +1,9 @@
 use super::action::{Action, ActionEntry};
 use crate::problem;
-use crate::utils::{Report, Result};
-use serde::Deserialize;
-use std::collections::hash_map;
-use std::collections::HashMap;
+use crate::utils::{Location, Report, Result};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::btree_map;
+use std::collections::BTreeMap;
 
 #[derive(Deserialize, Debug, PartialEq)]
 #[serde(transparent)]
@@ -13,20 +13,23 @@ pub struct GroupActions {
 
 #[derive(Debug)]
 pub struct Group {
-    actions: HashMap<String, Action>,
+    actions: BTreeMap<String, Action>,
 }
 
 impl Group {
     pub fn from_deserialized(deserialized_actions: GroupActions) -> Result<Self> {
-        let mut action_map = HashMap::new();
+        let mut action_map = BTreeMap::new();
         let mut report = Report::new();
 
         for action_entry in deserialized_actions.actions {
             if let Ok((name, action)) = Action::from_deserialized(action_entry) {
-                if action_map.contains_key(&name) {
-                    report.add(problem!("Duplicate action named '{}'", name))
-                } else {
-                    action_map.insert(name, action);
+                match action_map.entry(name) {
+                    btree_map::Entry::Occupied(entry) => {
+                        report.add(problem!("Duplicate action named '{}'", entry.key()))
+                    }
+                    btree_map::Entry::Vacant(entry) => {
+                        entry.insert(action);
+                    }
                 }
             }
         }
@@ -36,27 +39,164 @@ impl Group {
         })
     }
 
+    /// Finds where each action is declared in the source of the group file.
+    ///
+    /// The locations are best-effort: serde_yaml doesn't keep the position of the values, so the
+    /// source is scanned for the `name` keys of the items of the top-level sequence. Keys nested
+    /// deeper (in a command written as a block scalar for example) are ignored, and the actions
+    /// written as flow mappings (`- { name: some-action, ... }`) are left without a location.
+    pub(crate) fn locate_actions(&mut self, file: &str, source: &str) {
+        let mut item_indent = None;
+        let mut key_indent = None;
+        let mut awaiting_key = false;
+
+        for (line_index, line) in source.lines().enumerate() {
+            let content = line.trim_start();
+            let indent = line.len() - content.len();
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+
+            let item = content
+                .strip_prefix('-')
+                .filter(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+            let key = match item {
+                Some(rest) if *item_indent.get_or_insert(indent) == indent => {
+                    let key = rest.trim_start();
+                    awaiting_key = key.is_empty();
+                    key_indent = Some(line.len() - key.len()).filter(|_| !awaiting_key);
+                    key
+                }
+                _ if awaiting_key && item_indent.is_some_and(|item| indent > item) => {
+                    awaiting_key = false;
+                    key_indent = Some(indent);
+                    content
+                }
+                _ if key_indent == Some(indent) => content,
+                _ => continue,
+            };
+
+            if let Some(name) = name_key_value(key) {
+                if let Some(action) = self.actions.get_mut(name) {
+                    if action.location().is_none() {
+                        action.set_location(Location::new(
+                            file.to_owned(),
+                            line_index + 1,
+                            line.len() - key.len() + 1,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn action(&self, name: &str) -> Option<&Action> {
+        self.actions.get(name)
+    }
+
     pub fn action_count(&self) -> usize {
         self.actions.len()
     }
 
-    pub fn enumerate_actions(&self) -> hash_map::Iter<'_, String, Action> {
+    /// The actions of the group, sorted by name.
+    pub fn enumerate_actions(&self) -> btree_map::Iter<'_, String, Action> {
         self.actions.iter()
     }
 
-    pub fn into_enumerated_actions(self) -> hash_map::IntoIter<String, Action> {
+    pub fn into_enumerated_actions(self) -> btree_map::IntoIter<String, Action> {
         self.actions.into_iter()
     }
 
-    pub fn enumerate_action_names(&self) -> hash_map::Keys<'_, String, Action> {
+    pub fn enumerate_action_names(&self) -> btree_map::Keys<'_, String, Action> {
         self.actions.keys()
     }
 }
 
+impl Serialize for Group {
+    /// Serializes the actions by name.
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_map(&self.actions)
+    }
+}
+
+/// Adds an action at the end of the source of a group file, leaving the rest of the file as it
+/// is. The new source is checked before being returned.
+///
+/// The action is indented like the existing ones, and replaces the sequence if it is written as
+/// `[]`. Other flow sequences can't be appended to.
+pub fn append_action_entry(source: &str, entry: &ActionEntry) -> Result<String> {
+    let snippet = serde_yaml::to_string(&[entry])?;
+
+    let mut lines = source.split_inclusive('\n');
+    let mut appended = String::new();
+    let mut indent = "";
+    for line in lines.by_ref() {
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('#') || content == "---" {
+            appended.push_str(line);
+            continue;
+        }
+        if content.starts_with('-') {
+            indent = &line[..line.len() - line.trim_start().len()];
+            appended.push_str(line);
+            break;
+        }
+        let flow = content.split(" #").next().unwrap_or_default().trim_end();
+        if flow == "[]" {
+            // the empty sequence is replaced, with the lines after it
+            appended.push_str(&snippet);
+            appended.extend(lines);
+            return check_appended(appended);
+        }
+        return Err(problem!(
+            "The group file can't be appended to, the actions must be written as a list of \
+             '- name: ...' entries"
+        ));
+    }
+    appended.extend(lines);
+
+    if !appended.is_empty() && !appended.ends_with('\n') {
+        appended.push('\n');
+    }
+    for line in snippet.lines() {
+        appended.push_str(indent);
+        appended.push_str(line);
+        appended.push('\n');
+    }
+    check_appended(appended)
+}
+
+fn check_appended(appended: String) -> Result<String> {
+    let actions = serde_yaml::from_str::<GroupActions>(&appended)
+        .map_err(|error| problem!("The group file can't be appended to").because(error))?;
+    Group::from_deserialized(actions)?;
+
+    Ok(appended)
+}
+
+/// Returns the value of a key like `name: some-action`, the key and the value being possibly quoted.
+fn name_key_value(key: &str) -> Option<&str> {
+    let value = ["name:", "'name':", "\"name\":"]
+        .iter()
+        .find_map(|prefix| key.strip_prefix(prefix))?;
+    if !(value.is_empty() || value.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let value = value.trim();
+
+    let unquoted = ['\'', '"']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.split_once(*quote))
+        .map(|(unquoted, _)| unquoted);
+    // an unquoted value stops where a comment starts
+    Some(unquoted.unwrap_or_else(|| value.split(" #").next().unwrap_or_default().trim_end()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::actions::action::fakes::*;
+    use crate::actions::{Commands, HostRef, Trigger};
     use indoc::indoc;
 
     #[test]
@@ -105,4 +245,163 @@ mod tests {
         assert!(parsed.is_ok());
         assert!(Group::from_deserialized(parsed.unwrap()).is_err())
     }
+
+    #[test]
+    fn append_to_group() {
+        let source = indoc! {"
+            # deployment actions
+            - name: build
+              on: /build
+              run: make # the default target
+        "};
+        let entry = ActionEntry::new(
+            String::from("deploy"),
+            Trigger::parse("/deploy/<env>").unwrap(),
+            Commands::new(vec![
+                String::from("./deploy.sh"),
+                String::from("./notify.sh"),
+            ]),
+        )
+        .with_origin(HostRef::GitHub)
+        .with_secret(String::from("abc"));
+
+        let appended = append_action_entry(source.trim_end(), &entry).unwrap();
+
+        assert_eq!(
+            appended,
+            indoc! {"
+                # deployment actions
+                - name: build
+                  on: /build
+                  run: make # the default target
+                - name: deploy
+                  on: POST /deploy/<env>
+                  from: github
+                  secret: abc
+                  run: |-
+                    ./deploy.sh
+                    ./notify.sh
+            "}
+        );
+    }
+
+    #[test]
+    fn append_to_empty_group() {
+        let entry = ActionEntry::new(
+            String::from("hello"),
+            Trigger::parse("GET /hello").unwrap(),
+            Commands::new(vec![String::from("echo 'Hello, world!'")]),
+        );
+
+        let appended = append_action_entry("", &entry).unwrap();
+
+        assert_eq!(
+            appended,
+            "- name: hello\n  on: GET /hello\n  run: echo 'Hello, world!'\n"
+        );
+    }
+
+    #[test]
+    fn append_duplicate() {
+        let entry = ActionEntry::new(
+            String::from("build"),
+            Trigger::parse("/build").unwrap(),
+            Commands::new(vec![String::from("make")]),
+        );
+
+        assert!(append_action_entry("- name: build\n  on: /other\n  run: make\n", &entry).is_err());
+    }
+
+    #[test]
+    fn append_to_other_styles() {
+        let entry = ActionEntry::new(
+            String::from("build"),
+            Trigger::parse("/build").unwrap(),
+            Commands::new(vec![String::from("make")]),
+        );
+
+        let appended = append_action_entry("# nothing yet\n[]\n", &entry).unwrap();
+        assert_eq!(
+            appended,
+            "# nothing yet\n- name: build\n  on: POST /build\n  run: make\n"
+        );
+
+        let appended =
+            append_action_entry("  - name: test\n    on: /test\n    run: make test", &entry)
+                .unwrap();
+        assert_eq!(
+            appended,
+            "  - name: test\n    on: /test\n    run: make test\n  - name: build\n    on: POST /build\n    run: make\n"
+        );
+
+        let error = append_action_entry("[{ name: test, on: /test, run: make }]\n", &entry);
+        assert!(error
+            .unwrap_err()
+            .to_string()
+            .contains("list of '- name: ...' entries"));
+    }
+
+    #[test]
+    fn locate_actions() {
+        let source = indoc! {"
+            # some comment
+            - name: action1
+              on: POST /test/action
+              run: some-command
+            -   name: 'action2'
+                on: POST /test/action
+                run: some-command
+        "};
+        let parsed = serde_yaml::from_str::<GroupActions>(source);
+        let mut group = Group::from_deserialized(parsed.unwrap()).unwrap();
+
+        group.locate_actions("group.yml", source);
+
+        let location = group.actions["action1"].location().unwrap();
+        assert_eq!(location.to_string(), "in file group.yml, line 2, column 3");
+        let location = group.actions["action2"].location().unwrap();
+        assert_eq!(location.to_string(), "in file group.yml, line 5, column 5");
+    }
+
+    #[test]
+    fn locate_actions_skips_nested_keys() {
+        let source = indoc! {"
+            - name: action1
+              on: POST /test/action
+              run: |
+                name: action2
+                some-command
+            -
+              \"name\": action2 # the second one
+              on: POST /test/action
+              run: some-command
+        "};
+        let parsed = serde_yaml::from_str::<GroupActions>(source);
+        let mut group = Group::from_deserialized(parsed.unwrap()).unwrap();
+
+        group.locate_actions("group.yml", source);
+
+        let location = group.actions["action1"].location().unwrap();
+        assert_eq!(location.to_string(), "in file group.yml, line 1, column 3");
+        let location = group.actions["action2"].location().unwrap();
+        assert_eq!(location.to_string(), "in file group.yml, line 7, column 3");
+    }
+
+    #[test]
+    fn locate_actions_in_flow_mappings() {
+        let source = indoc! {"
+            - { name: action1, on: POST /test/action, run: some-command }
+            - name: action2
+              on: POST /test/action
+              run: some-command
+        "};
+        let parsed = serde_yaml::from_str::<GroupActions>(source);
+        let mut group = Group::from_deserialized(parsed.unwrap()).unwrap();
+
+        group.locate_actions("group.yml", source);
+
+        assert!(group.actions["action1"].location().is_none());
+        let location = group.actions["action2"].location().unwrap();
+        assert_eq!(location.to_string(), "in file group.yml, line 2, column 3");
+    }
 }
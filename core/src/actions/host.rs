@@ -1,5 +1,5 @@
 use serde::de::{Error, Visitor};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{self, Display, Formatter};
 
 #[derive(Debug, PartialEq)]
@@ -20,6 +20,28 @@ impl<'de> Deserialize<'de> for HostRef {
     }
 }
 
+impl HostRef {
+    /// Reads a host as it is written in the `from` field of the actions.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "gh" | "github" => HostRef::GitHub,
+            "*" => HostRef::Any,
+            _ => HostRef::Custom(String::from(value)),
+        }
+    }
+}
+
+impl Serialize for HostRef {
+    /// Serializes the host as it is written in the group files.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            HostRef::GitHub => serializer.serialize_str("github"),
+            HostRef::Any => serializer.serialize_str("*"),
+            HostRef::Custom(name) => serializer.serialize_str(name),
+        }
+    }
+}
+
 impl Display for HostRef {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
@@ -42,11 +64,7 @@ impl<'de> Visitor<'de> for HostVisitor {
     where
         E: Error,
     {
-        match v {
-            "gh" | "github" => Ok(HostRef::GitHub),
-            "*" => Ok(HostRef::Any),
-            _ => Ok(HostRef::Custom(String::from(v))),
-        }
+        Ok(HostRef::parse(v))
     }
 
     fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
@@ -0,0 +1,161 @@
+use super::{
+    read_all_groups, Action, Dependencies, ExecutionPlan, Group, Method, RoutingTable,
+    TriggerParameters,
+};
+use crate::enabled_actions::{load_enabled_actions, EnabledList};
+use crate::utils::{ActionPath, Result};
+use std::collections::{btree_map, BTreeMap};
+
+/// All the actions as the server uses them: the execution plan of each action, and the routes of
+/// the enabled ones.
+#[derive(Debug)]
+pub struct ActionIndex {
+    by_path: BTreeMap<ActionPath, Action>,
+    plans: BTreeMap<ActionPath, ExecutionPlan>,
+    routes: RoutingTable,
+    enabled: EnabledList,
+}
+
+impl ActionIndex {
+    /// Reads the groups and the enabled actions list, failing if any of the actions can't be
+    /// ordered or routed.
+    pub fn load() -> Result<Self> {
+        Self::build(read_all_groups()?, load_enabled_actions()?)
+    }
+
+    pub fn build<I>(groups: I, enabled: EnabledList) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, Group)>,
+    {
+        let mut by_path = BTreeMap::new();
+        for (group_name, group) in groups {
+            for (action_name, action) in group.into_enumerated_actions() {
+                by_path.insert(
+                    ActionPath::from_parts(group_name.clone(), action_name),
+                    action,
+                );
+            }
+        }
+
+        let plans = by_path
+            .iter()
+            .map(|(path, action)| (path.clone(), action))
+            .collect::<Dependencies>()
+            .plans()?;
+        let routes = RoutingTable::build(
+            by_path
+                .iter()
+                .filter(|(path, _)| enabled.is_path_enabled(path)),
+        )?;
+
+        Ok(Self {
+            by_path,
+            plans,
+            routes,
+            enabled,
+        })
+    }
+
+    /// The enabled actions triggered by a request, with the values of the parameters of their
+    /// trigger. The path is given as decoded segments.
+    pub fn lookup<'a, S>(
+        &self,
+        method: Method,
+        segments: S,
+    ) -> Vec<(&ActionPath, &Action, TriggerParameters)>
+    where
+        S: IntoIterator<Item = &'a str>,
+    {
+        self.routes
+            .lookup(method, segments)
+            .into_iter()
+            .filter_map(|(path, parameters)| Some((path, self.by_path.get(path)?, parameters)))
+            .collect()
+    }
+
+    /// The disabled actions that would be triggered by a request if they were enabled.
+    pub fn lookup_disabled<'a, S>(&self, method: Method, segments: S) -> Vec<&ActionPath>
+    where
+        S: IntoIterator<Item = &'a str> + Clone,
+    {
+        self.by_path
+            .iter()
+            .filter(|(path, _)| !self.is_enabled(path))
+            .filter(|(_, action)| action.trigger().matches(method, segments.clone()).is_some())
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    pub fn get(&self, path: &ActionPath) -> Option<(&ActionPath, &Action)> {
+        self.by_path.get_key_value(path)
+    }
+
+    /// The actions to run, in order, when an action is triggered.
+    pub fn plan(&self, path: &ActionPath) -> Option<&ExecutionPlan> {
+        self.plans.get(path)
+    }
+
+    pub fn is_enabled(&self, path: &ActionPath) -> bool {
+        self.enabled.is_path_enabled(path)
+    }
+
+    pub fn enabled_count(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// All the actions, sorted by path.
+    pub fn actions(&self) -> btree_map::Iter<'_, ActionPath, Action> {
+        self.by_path.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::parse_group;
+    use indoc::indoc;
+
+    fn index(enabled: &[&str]) -> Result<ActionIndex> {
+        let group = parse_group(
+            "site.yml",
+            indoc! {"
+                - name: build
+                  on: /build/<target>
+                  run: make
+                - name: deploy
+                  on: /deploy
+                  before: build
+                  run: ./deploy.sh
+            "},
+        )
+        .unwrap();
+        let mut list = EnabledList::default();
+        for path in enabled {
+            list.enable_path(ActionPath::parse(path).unwrap());
+        }
+        ActionIndex::build([(String::from("site"), group)], list)
+    }
+
+    #[test]
+    fn only_enabled_actions_are_routed() {
+        let index = index(&["site/deploy"]).unwrap();
+
+        let matched = index.lookup(Method::Post, ["deploy"]);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].0.to_string(), "site/deploy");
+        assert!(index.lookup(Method::Post, ["build", "all"]).is_empty());
+        assert_eq!(
+            index.lookup_disabled(Method::Post, ["build", "all"]),
+            [&ActionPath::parse("site/build").unwrap()]
+        );
+        assert_eq!(index.enabled_count(), 1);
+    }
+
+    #[test]
+    fn plans() {
+        let index = index(&[]).unwrap();
+
+        let plan = index.plan(&ActionPath::parse("site/deploy").unwrap());
+        assert_eq!(plan.unwrap().to_string(), "site/build -> site/deploy");
+    }
+}
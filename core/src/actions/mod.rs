@@ -1,30 +1,48 @@
 use crate::actions::group::GroupActions;
 use crate::problem;
-use crate::utils::{path, Location, Result};
-use std::collections::HashMap;
+use crate::utils::{path, write_new_file, Location, Result};
+use std::collections::BTreeMap;
 use std::collections::HashSet;
-use std::fs::{read_dir, DirEntry, File, ReadDir};
-use std::path::Path;
+use std::fs::{self, read_dir, read_to_string, DirEntry, Permissions, ReadDir};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 
 mod action;
 mod commands;
+mod dependencies;
 mod group;
 mod host;
+mod index;
+mod plan;
 mod refs;
+mod response;
+mod routes;
+mod trigger;
 
-pub use action::Action;
+pub use action::{Action, ActionEntry, REDACTED_SECRET};
 pub use commands::Commands;
-pub use group::Group;
+pub use dependencies::{Dependencies, Reference, ReferenceKind};
+pub use group::{append_action_entry, Group};
 pub use host::HostRef;
+pub use index::ActionIndex;
+pub use plan::ExecutionPlan;
+pub use response::{ResponseMode, WaitOptions};
+pub use routes::RoutingTable;
+pub use trigger::{
+    parameter_variable, Method, Segment, Trigger, TriggerParameters, RESERVED_PATHS,
+};
+
+/// The mode of new group files, which the server must be able to read whoever runs it.
+const NEW_GROUP_FILE_MODE: u32 = 0o644;
 
 pub type AllGroupsResults = Result<Vec<(String, Result<Group>)>>;
 
 pub struct AllGroups {
-    groups: HashMap<String, Group>,
+    groups: BTreeMap<String, Group>,
 }
 
 pub fn read_all_groups() -> Result<AllGroups> {
-    let mut groups_map = HashMap::new();
+    let mut groups_map = BTreeMap::new();
 
     for (name, group_result) in try_read_all_groups()? {
         groups_map.insert(name, group_result?);
@@ -34,13 +52,20 @@ pub fn read_all_groups() -> Result<AllGroups> {
 }
 
 pub fn try_read_all_groups() -> AllGroupsResults {
-    let entries = list_action_files()?;
+    try_read_groups_in(Path::new(path::ACTIONS_CONFIG_DIR))
+}
+
+/// Reads the groups of an actions directory other than the one of the server. The groups are
+/// sorted by name.
+pub fn try_read_groups_in(dir: &Path) -> AllGroupsResults {
+    let mut entries: Vec<DirEntry> = list_action_files(dir)?
+        .filter_map(|entry| entry.ok())
+        .collect();
+    entries.sort_by_key(DirEntry::file_name);
     let mut groups: Vec<(String, Result<Group>)> = Vec::new();
     let mut duplicates: HashSet<String> = HashSet::new();
 
-    for (name, group_result) in
-        entries.filter_map(|entry| entry.ok().and_then(read_group_from_entry))
-    {
+    for (name, group_result) in entries.into_iter().filter_map(read_group_from_entry) {
         if duplicates.contains(&name) {
             groups.push((name, Err(problem!("Duplicate group."))))
         } else {
@@ -53,7 +78,14 @@ pub fn try_read_all_groups() -> AllGroupsResults {
 }
 
 pub fn read_group_by_name(name: &'_ str) -> Result<Group> {
-    let path = list_action_files()?
+    let path = find_group_file(name)?.ok_or(problem!("Group '{}' not found.", name))?;
+
+    read_group(path)
+}
+
+/// Finds the file of a group, whatever its extension is.
+pub fn find_group_file(name: &str) -> Result<Option<PathBuf>> {
+    Ok(list_action_files(Path::new(path::ACTIONS_CONFIG_DIR))?
         .filter_map(|res| match res {
             Ok(entry) => Some(entry.path()),
             Err(_) => None,
@@ -64,10 +96,63 @@ pub fn read_group_by_name(name: &'_ str) -> Result<Group> {
                     Some(file_stem) => file_stem == name,
                     None => false,
                 }
-        })
-        .ok_or(problem!("Group '{}' not found.", name))?;
+        }))
+}
 
-    read_group(path)
+/// The file in which a new group is created. The name must not lead outside of the actions
+/// directory.
+pub fn new_group_file(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains('/') || name.contains("..") {
+        return Err(problem!("Invalid group name '{}'", name));
+    }
+    Ok(Path::new(path::ACTIONS_CONFIG_DIR).join(format!("{}.yml", name)))
+}
+
+/// Writes the source of a group next to the actions directory, then moves it over the group file
+/// so that the server never reads a partially written group. The file keeps its mode and its
+/// owner, and a new file gets the owner and the group of the actions directory.
+pub fn replace_group_file(file: &Path, source: &str) -> Result<()> {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let staging = dir.parent().unwrap_or(dir).join(format!(".{}.edit", name));
+
+    let (mode, uid, gid) = match fs::metadata(file) {
+        Ok(metadata) => (metadata.mode() & 0o7777, metadata.uid(), metadata.gid()),
+        Err(_) => {
+            let metadata = fs::metadata(dir).map_err(|err| {
+                problem!("Unable to read the owner of {}", dir.display()).because(err)
+            })?;
+            (NEW_GROUP_FILE_MODE, metadata.uid(), metadata.gid())
+        }
+    };
+
+    // a file left behind by an interrupted edit is replaced
+    let _ = fs::remove_file(&staging);
+    write_new_file(&staging, source, 0o600)?;
+    let moved = set_mode_and_owner(&staging, mode, uid, gid).and_then(|()| {
+        fs::rename(&staging, file)
+            .map_err(|err| problem!("Unable to replace {}", file.display()).because(err))
+    });
+    if moved.is_err() {
+        let _ = fs::remove_file(&staging);
+    }
+    moved
+}
+
+fn set_mode_and_owner(file: &Path, mode: u32, uid: u32, gid: u32) -> Result<()> {
+    let metadata = fs::metadata(file)?;
+    // only root can give a file away, so the owner is only changed when it has to be
+    if metadata.uid() != uid || metadata.gid() != gid {
+        std::os::unix::fs::chown(file, Some(uid), Some(gid)).map_err(|err| {
+            problem!("Unable to give {} to its owner", file.display()).because(err)
+        })?;
+    }
+    // the mode is set after the owner, since changing the owner may clear the setuid bits
+    fs::set_permissions(file, Permissions::from_mode(mode))?;
+    Ok(())
 }
 
 fn read_group_from_entry(entry: DirEntry) -> Option<(String, Result<Group>)> {
@@ -80,14 +165,19 @@ fn read_group_from_entry(entry: DirEntry) -> Option<(String, Result<Group>)> {
 fn read_group<P: AsRef<Path>>(path: P) -> Result<Group> {
     let path_str = path.as_ref().as_os_str().to_string_lossy().into_owned();
 
-    let file = File::open(path)?;
+    let source = read_to_string(path)?;
+
+    parse_group(&path_str, &source)
+}
 
-    let actions = serde_yaml::from_reader::<_, GroupActions>(file).map_err(|yaml_err| {
+/// Parses the source of a group file. The file is only used to locate the errors.
+pub fn parse_group(path_str: &str, source: &str) -> Result<Group> {
+    let actions = serde_yaml::from_str::<GroupActions>(source).map_err(|yaml_err| {
         let err = problem!("unable to parse file {}", path_str);
 
         let location = yaml_err
             .location()
-            .map(|yaml_loc| Location::new(path_str, yaml_loc.line(), yaml_loc.column()));
+            .map(|yaml_loc| Location::new(path_str.to_owned(), yaml_loc.line(), yaml_loc.column()));
         let cause = problem!(yaml_err);
 
         err.because(match location {
@@ -96,20 +186,80 @@ fn read_group<P: AsRef<Path>>(path: P) -> Result<Group> {
         })
     })?;
 
-    Group::from_deserialized(actions)
+    let mut group = Group::from_deserialized(actions)?;
+    group.locate_actions(path_str, source);
+
+    Ok(group)
 }
 
-fn list_action_files() -> Result<ReadDir> {
-    read_dir(path::ACTIONS_CONFIG_DIR)
-        .map_err(|err| problem!("Unable to read actions").because(err))
+fn list_action_files(dir: &Path) -> Result<ReadDir> {
+    read_dir(dir).map_err(|err| problem!("Unable to read actions").because(err))
 }
 
 impl IntoIterator for AllGroups {
     type Item = (String, Group);
 
-    type IntoIter = std::collections::hash_map::IntoIter<String, Group>;
+    type IntoIter = std::collections::btree_map::IntoIter<String, Group>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.groups.into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_file_names() {
+        assert!(new_group_file("website").is_ok());
+        assert!(new_group_file("").is_err());
+        assert!(new_group_file("../../x").is_err());
+        assert!(new_group_file("a/b").is_err());
+        assert!(new_group_file("..").is_err());
+    }
+
+    fn actions_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!(
+                "maidono-group-file-{}-{}",
+                name,
+                std::process::id()
+            ))
+            .join("actions");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn new_group_files_are_readable_by_the_server() {
+        let dir = actions_dir("new");
+        let file = dir.join("site.yml");
+
+        replace_group_file(&file, "[]\n").unwrap();
+
+        let metadata = fs::metadata(&file).unwrap();
+        let dir_metadata = fs::metadata(&dir).unwrap();
+        assert_eq!(metadata.mode() & 0o7777, 0o644);
+        assert_eq!(metadata.uid(), dir_metadata.uid());
+        assert_eq!(metadata.gid(), dir_metadata.gid());
+        assert_eq!(read_to_string(&file).unwrap(), "[]\n");
+        assert!(!dir.parent().unwrap().join(".site.yml.edit").exists());
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn replaced_group_files_keep_their_mode() {
+        let dir = actions_dir("replaced");
+        let file = dir.join("site.yml");
+        fs::write(&file, "[]\n").unwrap();
+        fs::set_permissions(&file, Permissions::from_mode(0o640)).unwrap();
+
+        replace_group_file(&file, "- name: build\n").unwrap();
+
+        assert_eq!(fs::metadata(&file).unwrap().mode() & 0o7777, 0o640);
+        assert_eq!(read_to_string(&file).unwrap(), "- name: build\n");
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+}
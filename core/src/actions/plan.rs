@@ -0,0 +1,27 @@
+use crate::utils::ActionPath;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// The actions to run, in order, when an action is triggered.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct ExecutionPlan {
+    steps: Vec<ActionPath>,
+}
+
+impl ExecutionPlan {
+    pub fn new(steps: Vec<ActionPath>) -> Self {
+        Self { steps }
+    }
+
+    pub fn steps(&self) -> &[ActionPath] {
+        &self.steps
+    }
+}
+
+impl Display for ExecutionPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let steps: Vec<String> = self.steps.iter().map(ToString::to_string).collect();
+        f.write_str(&steps.join(" -> "))
+    }
+}
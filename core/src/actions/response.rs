@@ -0,0 +1,182 @@
+use crate::problem;
+use crate::utils::{Error, Result};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+
+pub const DEFAULT_WAIT_TIMEOUT: u64 = 60;
+pub const DEFAULT_ERROR_STATUS: u16 = 500;
+
+/// When the webhook server responds to a request that triggered an action.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(try_from = "ResponseEntry")]
+pub enum ResponseMode {
+    /// Respond as soon as the action is started.
+    #[default]
+    Immediate,
+    /// Wait for the actions to finish and respond with their result.
+    Wait(WaitOptions),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaitOptions {
+    timeout: u64,
+    output: bool,
+    error_status: u16,
+}
+
+impl ResponseMode {
+    pub fn is_immediate(&self) -> bool {
+        matches!(self, ResponseMode::Immediate)
+    }
+}
+
+impl WaitOptions {
+    /// How long to wait for the actions, in seconds.
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+
+    /// Whether the output of the commands is included in the response.
+    pub fn includes_output(&self) -> bool {
+        self.output
+    }
+
+    /// The HTTP status of the response when the actions fail or time out.
+    pub fn error_status(&self) -> u16 {
+        self.error_status
+    }
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_WAIT_TIMEOUT,
+            output: false,
+            error_status: DEFAULT_ERROR_STATUS,
+        }
+    }
+}
+
+impl Serialize for ResponseMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            ResponseMode::Immediate => serializer.serialize_str("immediate"),
+            ResponseMode::Wait(options) => {
+                let mut state = serializer.serialize_struct("ResponseMode", 4)?;
+                state.serialize_field("mode", "wait")?;
+                state.serialize_field("timeout", &options.timeout)?;
+                state.serialize_field("output", &options.output)?;
+                state.serialize_field("error_status", &options.error_status)?;
+                state.end()
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ModeKeyword {
+    Immediate,
+    Wait,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct DetailedResponseEntry {
+    mode: ModeKeyword,
+    #[serde(default)]
+    timeout: Option<u64>,
+    #[serde(default)]
+    output: Option<bool>,
+    #[serde(default)]
+    error_status: Option<u16>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(untagged, expecting = "'immediate', 'wait' or response options")]
+enum ResponseEntry {
+    Keyword(ModeKeyword),
+    Detailed(DetailedResponseEntry),
+}
+
+impl TryFrom<ResponseEntry> for ResponseMode {
+    type Error = Error;
+
+    fn try_from(entry: ResponseEntry) -> Result<Self> {
+        match entry {
+            ResponseEntry::Keyword(ModeKeyword::Immediate) => Ok(ResponseMode::Immediate),
+            ResponseEntry::Keyword(ModeKeyword::Wait) => {
+                Ok(ResponseMode::Wait(WaitOptions::default()))
+            }
+            ResponseEntry::Detailed(DetailedResponseEntry {
+                mode: ModeKeyword::Immediate,
+                timeout: None,
+                output: None,
+                error_status: None,
+            }) => Ok(ResponseMode::Immediate),
+            ResponseEntry::Detailed(DetailedResponseEntry {
+                mode: ModeKeyword::Immediate,
+                ..
+            }) => Err(problem!(
+                "timeout, output and error_status can only be used with the 'wait' mode"
+            )),
+            ResponseEntry::Detailed(detailed) => {
+                let defaults = WaitOptions::default();
+                let options = WaitOptions {
+                    timeout: detailed.timeout.unwrap_or(defaults.timeout),
+                    output: detailed.output.unwrap_or(defaults.output),
+                    error_status: detailed.error_status.unwrap_or(defaults.error_status),
+                };
+                if !(400..=599).contains(&options.error_status) {
+                    return Err(problem!(
+                        "error_status must be between 400 and 599, got {}",
+                        options.error_status
+                    ));
+                }
+                Ok(ResponseMode::Wait(options))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn yaml_keywords() {
+        let parsed = serde_yaml::from_str::<ResponseMode>("immediate");
+        assert_eq!(parsed.unwrap(), ResponseMode::Immediate);
+
+        let parsed = serde_yaml::from_str::<ResponseMode>("wait");
+        assert_eq!(parsed.unwrap(), ResponseMode::Wait(WaitOptions::default()));
+    }
+
+    #[test]
+    fn yaml_options() {
+        let parsed = serde_yaml::from_str::<ResponseMode>(indoc! {"
+            mode: wait
+            timeout: 10
+            output: true
+            error_status: 502
+        "});
+
+        assert_eq!(
+            parsed.unwrap(),
+            ResponseMode::Wait(WaitOptions {
+                timeout: 10,
+                output: true,
+                error_status: 502,
+            })
+        );
+    }
+
+    #[test]
+    fn yaml_invalid() {
+        assert!(serde_yaml::from_str::<ResponseMode>("later").is_err());
+        assert!(serde_yaml::from_str::<ResponseMode>("{ mode: immediate, timeout: 3 }").is_err());
+        assert!(serde_yaml::from_str::<ResponseMode>("{ mode: wait, error_status: 200 }").is_err());
+        assert!(serde_yaml::from_str::<ResponseMode>("{ mode: wait, retries: 3 }").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use super::{Action, Method, Segment, Trigger, TriggerParameters};
+use crate::problem;
+use crate::utils::{ActionPath, Report, Result};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// An index of the triggers of the enabled actions.
+///
+/// Triggers without parameters are looked up directly by method and path. Other triggers are
+/// tried in order of precedence: at the first segment where they differ, a static segment takes
+/// precedence over a parameter. A trigger may be used by several actions if they all opt in with
+/// `shared_trigger`.
+#[derive(Debug, Default)]
+pub struct RoutingTable {
+    exact: HashMap<Method, HashMap<Vec<String>, Vec<ActionPath>>>,
+    parameterized: Vec<(Trigger, ActionPath)>,
+}
+
+impl RoutingTable {
+    pub fn build<'a, I>(actions: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (&'a ActionPath, &'a Action)>,
+    {
+        let mut table = Self::default();
+        let mut claimed: HashMap<(Method, Shape), (&ActionPath, bool)> = HashMap::new();
+        let mut report = Report::new();
+
+        for (path, action) in actions {
+            let trigger = action.trigger();
+            match claimed.get(&(trigger.method(), shape(trigger))) {
+                Some((_, true)) if action.shares_trigger() => table.insert(trigger, path),
+                Some((other, _)) => report.add(action.locate(problem!(
+                    "Actions '{}' and '{}' both use the trigger '{}' without setting shared_trigger",
+                    other,
+                    path,
+                    trigger
+                ))),
+                None => {
+                    claimed.insert(
+                        (trigger.method(), shape(trigger)),
+                        (path, action.shares_trigger()),
+                    );
+                    table.insert(trigger, path);
+                }
+            }
+        }
+
+        table
+            .parameterized
+            .sort_by(|(a, _), (b, _)| compare_precedence(a, b));
+
+        report.wrap(table)
+    }
+
+    fn insert(&mut self, trigger: &Trigger, path: &ActionPath) {
+        match static_path(trigger) {
+            Some(static_path) => self
+                .exact
+                .entry(trigger.method())
+                .or_default()
+                .entry(static_path)
+                .or_default()
+                .push(path.clone()),
+            None => self.parameterized.push((trigger.clone(), path.clone())),
+        }
+    }
+
+    /// Finds the actions triggered by a request. The path is given as decoded segments.
+    pub fn lookup<'a, S>(&self, method: Method, path: S) -> Vec<(&ActionPath, TriggerParameters)>
+    where
+        S: IntoIterator<Item = &'a str>,
+    {
+        let segments: Vec<String> = path
+            .into_iter()
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_owned)
+            .collect();
+
+        let exact_match = self
+            .exact
+            .get(&method)
+            .and_then(|paths| paths.get(segments.as_slice()));
+        if let Some(actions) = exact_match {
+            return actions
+                .iter()
+                .map(|action| (action, TriggerParameters::new()))
+                .collect();
+        }
+
+        let matches =
+            |trigger: &Trigger| trigger.matches(method, segments.iter().map(String::as_str));
+        let best_match = self
+            .parameterized
+            .iter()
+            .find(|(trigger, _)| matches(trigger).is_some());
+
+        match best_match {
+            Some((best_trigger, _)) => self
+                .parameterized
+                .iter()
+                .filter(|(trigger, _)| shape(trigger) == shape(best_trigger))
+                .filter_map(|(trigger, action)| {
+                    matches(trigger).map(|parameters| (action, parameters))
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        let exact_count: usize = self
+            .exact
+            .values()
+            .flat_map(HashMap::values)
+            .map(Vec::len)
+            .sum();
+        exact_count + self.parameterized.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The segments of a trigger, ignoring the names of the parameters.
+type Shape<'a> = Vec<Option<&'a str>>;
+
+fn shape(trigger: &Trigger) -> Shape<'_> {
+    trigger
+        .segments()
+        .iter()
+        .map(|segment| match segment {
+            Segment::Static(value) => Some(value.as_str()),
+            Segment::Parameter(_) => None,
+        })
+        .collect()
+}
+
+fn static_path(trigger: &Trigger) -> Option<Vec<String>> {
+    shape(trigger)
+        .into_iter()
+        .map(|segment| segment.map(str::to_owned))
+        .collect()
+}
+
+/// Orders the triggers by comparing their segments one by one, a static segment coming before a
+/// parameter. When one trigger is the start of the other, the shorter one comes first.
+fn compare_precedence(a: &Trigger, b: &Trigger) -> Ordering {
+    let kinds = |trigger: &Trigger| {
+        trigger
+            .segments()
+            .iter()
+            .map(|segment| matches!(segment, Segment::Parameter(_)))
+            .collect::<Vec<bool>>()
+    };
+    kinds(a).cmp(&kinds(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::action::fakes::*;
+
+    fn build(actions: Vec<(&str, Action)>) -> Result<RoutingTable> {
+        let actions: Vec<(ActionPath, Action)> = actions
+            .into_iter()
+            .map(|(path, action)| (ActionPath::parse(path).unwrap(), action))
+            .collect();
+
+        RoutingTable::build(actions.iter().map(|(path, action)| (path, action)))
+    }
+
+    fn table(actions: &[(&str, &str)]) -> Result<RoutingTable> {
+        build(
+            actions
+                .iter()
+                .map(|(path, trigger)| (*path, make_test_action_with_trigger(trigger)))
+                .collect(),
+        )
+    }
+
+    fn lookup(table: &RoutingTable, method: Method, path: &str) -> Option<String> {
+        let actions: Vec<String> = table
+            .lookup(method, path.split('/'))
+            .into_iter()
+            .map(|(action, _)| action.to_string())
+            .collect();
+
+        if actions.is_empty() {
+            None
+        } else {
+            Some(actions.join(", "))
+        }
+    }
+
+    #[test]
+    fn exact_routes() {
+        let table = table(&[("g/a", "POST /a"), ("g/b", "GET /a"), ("g/c", "/a/b")]).unwrap();
+
+        assert_eq!(lookup(&table, Method::Post, "/a").unwrap(), "g/a");
+        assert_eq!(lookup(&table, Method::Get, "/a").unwrap(), "g/b");
+        assert_eq!(lookup(&table, Method::Post, "/a/b/").unwrap(), "g/c");
+        assert!(lookup(&table, Method::Put, "/a").is_none());
+        assert!(lookup(&table, Method::Post, "/b").is_none());
+    }
+
+    #[test]
+    fn parameterized_routes() {
+        let table = table(&[
+            ("g/any", "/<group>/<action>"),
+            ("g/deploy", "/deploy/<env>"),
+            ("g/deploy-prod", "/deploy/prod"),
+            ("g/build", "/<app>/build"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            lookup(&table, Method::Post, "/deploy/prod").unwrap(),
+            "g/deploy-prod"
+        );
+        assert_eq!(
+            lookup(&table, Method::Post, "/deploy/test").unwrap(),
+            "g/deploy"
+        );
+        assert_eq!(
+            lookup(&table, Method::Post, "/deploy/build").unwrap(),
+            "g/deploy"
+        );
+        assert_eq!(
+            lookup(&table, Method::Post, "/app/build").unwrap(),
+            "g/build"
+        );
+        assert_eq!(lookup(&table, Method::Post, "/app/test").unwrap(), "g/any");
+
+        let (_, parameters) = &table.lookup(Method::Post, "/deploy/test".split('/'))[0];
+        assert_eq!(parameters["env"], "test");
+    }
+
+    #[test]
+    fn precedence_is_a_total_order() {
+        let table = table(&[
+            ("g/branch", "/repo/<repo>/<branch>"),
+            ("g/hook", "/hook/<name>"),
+            ("g/push", "/repo/<repo>/push"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            lookup(&table, Method::Post, "/repo/x/push").unwrap(),
+            "g/push"
+        );
+        assert_eq!(
+            lookup(&table, Method::Post, "/repo/x/main").unwrap(),
+            "g/branch"
+        );
+        assert_eq!(lookup(&table, Method::Post, "/hook/x").unwrap(), "g/hook");
+    }
+
+    #[test]
+    fn conflicting_routes() {
+        assert!(table(&[("g/a", "POST /a"), ("h/b", "/a/")]).is_err());
+        assert!(table(&[("g/a", "/deploy/<env>"), ("g/b", "/deploy/<name>")]).is_err());
+        assert!(table(&[("g/a", "GET /a"), ("g/b", "POST /a")]).is_ok());
+    }
+
+    #[test]
+    fn shared_routes() {
+        let table = build(vec![
+            (
+                "g/deploy",
+                make_test_action_with_shared_trigger("/push/<repo>"),
+            ),
+            (
+                "g/docs",
+                make_test_action_with_shared_trigger("/push/<name>"),
+            ),
+            (
+                "h/notify",
+                make_test_action_with_shared_trigger("/push/<repo>"),
+            ),
+            ("h/ping", make_test_action_with_shared_trigger("/ping")),
+            ("h/pong", make_test_action_with_shared_trigger("/ping")),
+        ])
+        .unwrap();
+
+        let matched = table.lookup(Method::Post, "/push/maidono".split('/'));
+        assert_eq!(matched.len(), 3);
+        assert_eq!(matched[1].0.to_string(), "g/docs");
+        assert_eq!(matched[1].1["name"], "maidono");
+        assert_eq!(
+            lookup(&table, Method::Post, "/ping").unwrap(),
+            "h/ping, h/pong"
+        );
+    }
+
+    #[test]
+    fn shared_route_requires_every_action_to_opt_in() {
+        let result = build(vec![
+            ("g/a", make_test_action_with_shared_trigger("/a")),
+            ("g/b", make_test_action_with_trigger("/a")),
+        ]);
+
+        assert!(result.is_err());
+    }
+}
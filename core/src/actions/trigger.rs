@@ -0,0 +1,338 @@
+use crate::problem;
+use crate::utils::{Error, Result};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The values of the parameters of a trigger, by name.
+pub type TriggerParameters = BTreeMap<String, String>;
+
+/// The environment variable in which the commands receive a parameter (`<env>` becomes
+/// `MAIDONO_PARAM_ENV`).
+pub fn parameter_variable(name: &str) -> String {
+    format!("MAIDONO_PARAM_{}", name.to_ascii_uppercase()).replace('-', "_")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+pub const DEFAULT_METHOD: Method = Method::Post;
+
+/// The first segments of the paths used by the server itself, which triggers shouldn't use.
+pub const RESERVED_PATHS: [&str; 5] = ["api", "healthz", "readyz", "metrics", "runs"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Static(String),
+    Parameter(String),
+}
+
+/// The method and the path pattern of the requests that trigger an action, for example
+/// `POST /deploy/<env>`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(try_from = "String")]
+pub struct Trigger {
+    method: Method,
+    segments: Vec<Segment>,
+}
+
+impl Trigger {
+    pub fn parse(trigger: &str) -> Result<Self> {
+        let parts: Vec<&str> = trigger.split_whitespace().collect();
+        let (method, path) = match parts[..] {
+            [path] => (DEFAULT_METHOD, path),
+            [method, path] => (method.parse()?, path),
+            _ => return Err(problem!("Invalid trigger '{}'", trigger)),
+        };
+
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(Segment::parse)
+            .collect::<Result<Vec<Segment>>>()
+            .map_err(|error| problem!("Invalid trigger '{}'", trigger).because(error))?;
+
+        if segments.is_empty() {
+            return Err(problem!("The trigger '{}' has no path", trigger));
+        }
+        for (index, segment) in segments.iter().enumerate() {
+            if let Segment::Parameter(name) = segment {
+                if segments[..index].contains(segment) {
+                    return Err(problem!(
+                        "The parameter '{}' appears twice in trigger '{}'",
+                        name,
+                        trigger
+                    ));
+                }
+            }
+        }
+
+        Ok(Self { method, segments })
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// The reserved path this trigger collides with, if any.
+    pub fn reserved_path(&self) -> Option<&'static str> {
+        match self.segments.first() {
+            Some(Segment::Static(first)) => RESERVED_PATHS
+                .into_iter()
+                .find(|reserved| reserved == first),
+            _ => None,
+        }
+    }
+
+    /// Tests a request against this trigger, and extracts the values of the parameters if it
+    /// matches. The path is given as decoded segments.
+    pub fn matches<'a, S>(&self, method: Method, path: S) -> Option<TriggerParameters>
+    where
+        S: IntoIterator<Item = &'a str>,
+    {
+        if method != self.method {
+            return None;
+        }
+
+        let mut parameters = TriggerParameters::new();
+        let mut expected = self.segments.iter();
+        for actual in path.into_iter().filter(|segment| !segment.is_empty()) {
+            match expected.next()? {
+                Segment::Static(value) if value == actual => {}
+                Segment::Static(_) => return None,
+                Segment::Parameter(name) => {
+                    parameters.insert(name.clone(), actual.to_owned());
+                }
+            }
+        }
+
+        match expected.next() {
+            None => Some(parameters),
+            Some(_) => None,
+        }
+    }
+}
+
+impl TryFrom<String> for Trigger {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::parse(&value)
+    }
+}
+
+impl Serialize for Trigger {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.method)?;
+        for segment in &self.segments {
+            write!(f, "/{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl Segment {
+    fn parse(segment: &str) -> Result<Self> {
+        let parameter = segment
+            .strip_prefix('<')
+            .and_then(|rest| rest.strip_suffix('>'));
+
+        match parameter {
+            Some(name) => {
+                if !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    Ok(Segment::Parameter(name.to_owned()))
+                } else {
+                    Err(problem!("Invalid parameter name '{}'", name))
+                }
+            }
+            None => {
+                if segment.contains(['<', '>', '?', '#']) {
+                    Err(problem!("Invalid path segment '{}'", segment))
+                } else {
+                    Ok(Segment::Static(segment.to_owned()))
+                }
+            }
+        }
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Segment::Static(value) => write!(f, "{}", value),
+            Segment::Parameter(name) => write!(f, "<{}>", name),
+        }
+    }
+}
+
+impl FromStr for Method {
+    type Err = Error;
+
+    fn from_str(method: &str) -> Result<Self> {
+        match method.to_ascii_uppercase().as_str() {
+            "GET" => Ok(Method::Get),
+            "POST" => Ok(Method::Post),
+            "PUT" => Ok(Method::Put),
+            "PATCH" => Ok(Method::Patch),
+            "DELETE" => Ok(Method::Delete),
+            _ => Err(problem!("Unsupported method '{}'", method)),
+        }
+    }
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(path: &str) -> Vec<&str> {
+        path.split('/').collect()
+    }
+
+    #[test]
+    fn parse_method_and_path() {
+        let trigger = Trigger::parse("put /my/action").unwrap();
+
+        assert_eq!(trigger.method(), Method::Put);
+        assert_eq!(
+            trigger.segments(),
+            &[
+                Segment::Static(String::from("my")),
+                Segment::Static(String::from("action"))
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_default_method() {
+        let trigger = Trigger::parse("/my/action").unwrap();
+
+        assert_eq!(trigger.method(), Method::Post);
+    }
+
+    #[test]
+    fn normalize_path() {
+        let trigger = Trigger::parse("POST my//action/").unwrap();
+
+        assert_eq!(trigger.to_string(), "POST /my/action");
+    }
+
+    #[test]
+    fn parse_parameters() {
+        let trigger = Trigger::parse("POST /deploy/<env>/<app_name>").unwrap();
+
+        assert_eq!(
+            trigger.segments(),
+            &[
+                Segment::Static(String::from("deploy")),
+                Segment::Parameter(String::from("env")),
+                Segment::Parameter(String::from("app_name")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_malformed() {
+        assert!(Trigger::parse("").is_err());
+        assert!(Trigger::parse("POST /").is_err());
+        assert!(Trigger::parse("FETCH /my/action").is_err());
+        assert!(Trigger::parse("POST /my/action extra").is_err());
+        assert!(Trigger::parse("POST /deploy/<>").is_err());
+        assert!(Trigger::parse("POST /deploy/<env").is_err());
+        assert!(Trigger::parse("POST /deploy/<env>-prod").is_err());
+        assert!(Trigger::parse("POST /deploy/<a b>").is_err());
+        assert!(Trigger::parse("POST /deploy/<env>/<env>").is_err());
+        assert!(Trigger::parse("POST /deploy?env=prod").is_err());
+    }
+
+    #[test]
+    fn match_static() {
+        let trigger = Trigger::parse("POST /my/action").unwrap();
+
+        assert!(trigger
+            .matches(Method::Post, segments("/my/action"))
+            .is_some());
+        assert!(trigger.matches(Method::Post, segments("my/action/")).is_some());
+        assert!(trigger.matches(Method::Get, segments("/my/action")).is_none());
+        assert!(trigger.matches(Method::Post, segments("/my")).is_none());
+        assert!(trigger
+            .matches(Method::Post, segments("/my/action/more"))
+            .is_none());
+        assert!(trigger.matches(Method::Post, segments("/my/other")).is_none());
+    }
+
+    #[test]
+    fn match_parameters() {
+        let trigger = Trigger::parse("POST /deploy/<env>/<app>").unwrap();
+
+        let parameters = trigger
+            .matches(Method::Post, segments("/deploy/prod/website"))
+            .unwrap();
+
+        assert_eq!(parameters["env"], "prod");
+        assert_eq!(parameters["app"], "website");
+        assert!(trigger
+            .matches(Method::Post, segments("/deploy/prod"))
+            .is_none());
+    }
+
+    #[test]
+    fn name_parameter_variables() {
+        assert_eq!(parameter_variable("env"), "MAIDONO_PARAM_ENV");
+        assert_eq!(parameter_variable("app-name"), "MAIDONO_PARAM_APP_NAME");
+    }
+
+    #[test]
+    fn reserved_paths() {
+        let reserved = |trigger: &str| Trigger::parse(trigger).unwrap().reserved_path();
+
+        assert_eq!(reserved("GET /healthz"), Some("healthz"));
+        assert_eq!(reserved("/runs/<id>"), Some("runs"));
+        assert_eq!(reserved("GET /api/groups"), Some("api"));
+        assert_eq!(reserved("/deploy/healthz"), None);
+        assert_eq!(reserved("/<path>"), None);
+    }
+
+    #[test]
+    fn deserialize() {
+        let parsed = serde_yaml::from_str::<Trigger>("GET /some/path");
+
+        assert_eq!(parsed.unwrap(), Trigger::parse("GET /some/path").unwrap());
+
+        let parsed = serde_yaml::from_str::<Trigger>("GET /some/<path");
+
+        assert!(parsed.is_err());
+    }
+}
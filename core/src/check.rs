@@ -0,0 +1,297 @@
+//! Validation of a whole config directory, laid out like `/etc/maidono`, without a server.
+
+use crate::actions::{try_read_groups_in, Action, Dependencies, Group, HostRef, RoutingTable};
+use crate::enabled_actions::{load_enabled_actions_from, EnabledEntry, EnabledList};
+use crate::problem;
+use crate::utils::{ActionPath, Error};
+use std::path::Path;
+
+/// The problems found in a config directory. Errors would prevent the server from loading the
+/// actions, while warnings point at actions that probably don't behave as intended.
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub errors: Vec<Error>,
+    pub warnings: Vec<Error>,
+}
+
+impl CheckReport {
+    fn error(&mut self, error: Error) {
+        add_flattened(&mut self.errors, error);
+    }
+
+    fn warning(&mut self, warning: Error) {
+        add_flattened(&mut self.warnings, warning);
+    }
+}
+
+fn add_flattened(list: &mut Vec<Error>, error: Error) {
+    match error {
+        Error::Multiple(errors) => errors
+            .into_iter()
+            .for_each(|error| add_flattened(list, error)),
+        error => list.push(error),
+    }
+}
+
+pub fn check_config_dir(dir: &Path) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    let groups = match try_read_groups_in(&dir.join("actions")) {
+        Ok(groups) => groups,
+        Err(error) => {
+            report.error(error);
+            return report;
+        }
+    };
+    let mut valid_groups: Vec<(String, Group)> = Vec::new();
+    for (name, result) in groups {
+        match result {
+            Ok(group) => valid_groups.push((name, group)),
+            Err(error) => report.error(problem!("Invalid group '{}'", name).because(error)),
+        }
+    }
+
+    let actions: Vec<(ActionPath, &Action)> = valid_groups
+        .iter()
+        .flat_map(|(group_name, group)| {
+            group
+                .enumerate_actions()
+                .map(move |(name, action)| (ActionPath::from_parts(group_name, name), action))
+        })
+        .collect();
+
+    let dependencies = Dependencies::from_groups(
+        valid_groups
+            .iter()
+            .map(|(name, group)| (name.as_str(), group)),
+    );
+    // like the server, the actions must be ordered, not only reference existing actions
+    if let Err(error) = dependencies.plans() {
+        report.error(error);
+    }
+
+    for (path, action) in &actions {
+        check_action(&mut report, path, action);
+    }
+
+    let enabled_file = dir.join("enabled");
+    if enabled_file.exists() {
+        match load_enabled_actions_from(&enabled_file) {
+            Ok(enabled) => check_enabled_actions(&mut report, &enabled, &actions),
+            Err(error) => report.error(error),
+        }
+    } else {
+        report.warning(problem!(
+            "There is no enabled actions list at {}, the enabled actions were not checked",
+            enabled_file.display()
+        ));
+    }
+
+    report
+}
+
+fn check_action(report: &mut CheckReport, path: &ActionPath, action: &Action) {
+    match (action.origin(), action.secret()) {
+        (HostRef::GitHub, None) => report.warning(action.locate(problem!(
+            "Action '{}' accepts GitHub webhooks without a secret, anyone can trigger it",
+            path
+        ))),
+        (HostRef::Any, Some(_)) => report.warning(action.locate(problem!(
+            "Action '{}' has a secret but no origin to sign the requests, it will reject all of them",
+            path
+        ))),
+        _ => {}
+    }
+    if let HostRef::Custom(name) = action.origin() {
+        report.warning(action.locate(problem!(
+            "Action '{}' comes from the unknown origin '{}', the requests won't be checked",
+            path,
+            name
+        )));
+    }
+    if let Some(reserved) = action.trigger().reserved_path() {
+        report.warning(action.locate(problem!(
+            "The trigger of action '{}' uses the path '/{}', which is reserved by the server",
+            path,
+            reserved
+        )));
+    }
+}
+
+fn check_enabled_actions(
+    report: &mut CheckReport,
+    enabled: &EnabledList,
+    actions: &[(ActionPath, &Action)],
+) {
+    for entry in enabled.entries() {
+        match entry {
+            EnabledEntry::Action(path) => {
+                if !actions.iter().any(|(known, _)| known == path) {
+                    report.warning(problem!("Action '{}' is enabled but doesn't exist", path));
+                }
+            }
+            EnabledEntry::Group(group) => {
+                if !actions.iter().any(|(known, _)| known.group() == group) {
+                    report.warning(problem!("Group '{}' is enabled but has no actions", group));
+                }
+            }
+            EnabledEntry::Except(path) => {
+                if !actions.iter().any(|(known, _)| known == path) {
+                    report.warning(problem!("Action '{}' is excluded but doesn't exist", path));
+                }
+            }
+        }
+    }
+
+    let routes = RoutingTable::build(
+        actions
+            .iter()
+            .filter(|(path, _)| enabled.is_path_enabled(path))
+            .map(|(path, action)| (path, *action)),
+    );
+    if let Err(error) = routes {
+        report.error(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn config_dir(name: &str, groups: &[(&str, &str)], enabled: Option<&str>) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("maidono-check-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("actions")).unwrap();
+        for (group, source) in groups {
+            fs::write(dir.join("actions").join(format!("{}.yml", group)), source).unwrap();
+        }
+        if let Some(enabled) = enabled {
+            fs::write(dir.join("enabled"), enabled).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn valid_config() {
+        let dir = config_dir(
+            "valid",
+            &[(
+                "site",
+                indoc! {"
+                    - name: build
+                      on: /build
+                      run: make
+                    - name: deploy
+                      on: /deploy
+                      from: github
+                      secret: abc
+                      before: build
+                      run: ./deploy.sh
+                "},
+            )],
+            Some("action:site/deploy\n"),
+        );
+
+        let report = check_config_dir(&dir);
+
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn errors() {
+        let dir = config_dir(
+            "errors",
+            &[
+                ("broken", "- name: a\n  on: [\n"),
+                (
+                    "site",
+                    indoc! {"
+                        - name: one
+                          on: /same
+                          before: nowhere
+                          run: make
+                        - name: two
+                          on: /same
+                          run: make
+                    "},
+                ),
+            ],
+            Some("action:site/one\naction:site/two\n"),
+        );
+
+        let report = check_config_dir(&dir);
+
+        // the broken group, the reference and the trigger conflict
+        assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+        assert!(format!("{:?}", report.errors).contains("line 2, column 7"));
+    }
+
+    #[test]
+    fn ordering_conflict() {
+        let dir = config_dir(
+            "ordering",
+            &[(
+                "site",
+                indoc! {"
+                    - name: build
+                      on: /build
+                      run: make
+                    - name: deploy
+                      on: /deploy
+                      before: build
+                      after: build
+                      run: ./deploy.sh
+                "},
+            )],
+            Some("action:site/deploy\n"),
+        );
+
+        let report = check_config_dir(&dir);
+
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+    }
+
+    #[test]
+    fn warnings() {
+        let dir = config_dir(
+            "warnings",
+            &[(
+                "site",
+                indoc! {"
+                    - name: open
+                      on: /open
+                      from: github
+                      run: make
+                    - name: unsigned
+                      on: /unsigned
+                      secret: abc
+                      run: make
+                    - name: health
+                      on: GET /healthz
+                      run: make
+                "},
+            )],
+            Some("action:site/gone\ngroup:gone\nexcept:site/nothing\n"),
+        );
+
+        let report = check_config_dir(&dir);
+
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.warnings.len(), 6, "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn missing_enabled_list() {
+        let dir = config_dir("missing-enabled", &[], None);
+
+        let report = check_config_dir(&dir);
+
+        assert!(report.errors.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,279 @@
+//! The protocol used on the control socket of the server: each request is a JSON object on a
+//! single line, answered by a response on a single line too.
+
+use crate::actions::TriggerParameters;
+use crate::problem;
+use crate::utils::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long a client waits for the server to answer.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ControlRequest {
+    Status,
+    Reload,
+    ActiveRuns,
+    /// All the runs remembered by the server, starting with the most recent one.
+    History,
+    /// A run, with the result of each of its actions.
+    Run {
+        run: String,
+    },
+    /// The log of a run, starting at the entry `from` (entries are never removed from a log, so
+    /// a client can follow a run by asking for what comes after the entries it already has).
+    RunLog {
+        run: String,
+        #[serde(default)]
+        from: usize,
+    },
+    Cancel {
+        run: String,
+    },
+    Trigger {
+        action: String,
+        #[serde(default)]
+        parameters: TriggerParameters,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ControlResponse {
+    Status(ServerStatus),
+    Reloaded {
+        enabled_actions: usize,
+    },
+    Runs {
+        runs: Vec<RunSummary>,
+    },
+    Run {
+        run: RunSummary,
+        actions: Vec<ActionOutcome>,
+    },
+    RunLog {
+        run: RunSummary,
+        entries: Vec<LogEntry>,
+    },
+    Cancelled {
+        run: RunSummary,
+    },
+    Triggered {
+        run: RunSummary,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ServerStatus {
+    pub version: String,
+    pub started_at: String,
+    pub loaded_at: String,
+    pub enabled_actions: usize,
+    pub runs_in_progress: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct RunSummary {
+    pub id: String,
+    pub action: String,
+    pub chain: Vec<String>,
+    pub status: String,
+    pub started_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// The ID given by the sender of the webhook, if it gives one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ActionOutcome {
+    pub action: String,
+    pub status: String,
+    pub commands: Vec<CommandOutcome>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct CommandOutcome {
+    pub command: String,
+    pub exit_code: Option<i32>,
+}
+
+/// Something that happened during a run, with the time (in RFC 3339 format) when it happened.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub time: String,
+    #[serde(flatten)]
+    pub event: RunEvent,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent {
+    ActionStarted {
+        action: String,
+    },
+    ActionSkipped {
+        action: String,
+    },
+    CommandStarted {
+        command: String,
+    },
+    Output {
+        stream: OutputStream,
+        line: String,
+    },
+    CommandExited {
+        exit_code: Option<i32>,
+    },
+    /// Too much output was written, the rest of it is not in the log.
+    OutputTruncated,
+    Finished {
+        status: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl ControlResponse {
+    /// Turns the error responses into errors.
+    pub fn into_result(self) -> Result<Self> {
+        match self {
+            ControlResponse::Error { message } => Err(Error::from(message.as_str())),
+            response => Ok(response),
+        }
+    }
+}
+
+impl RunSummary {
+    pub fn is_finished(&self) -> bool {
+        self.status != "running"
+    }
+
+    /// Tells if a command of the run failed, or if it did not finish in time.
+    pub fn has_failed(&self) -> bool {
+        self.status == "failed" || self.status == "timeout"
+    }
+}
+
+impl ControlRequest {
+    /// Sends this request to the server listening on `socket`, and waits for its response.
+    pub fn send(&self, socket: &Path) -> Result<ControlResponse> {
+        let stream = UnixStream::connect(socket).map_err(|error| {
+            problem!("Unable to connect to {}", socket.display()).because(error)
+        })?;
+        stream.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+
+        (&stream).write_all(encode_message(self)?.as_bytes())?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        if line.is_empty() {
+            return Err(problem!(
+                "The server closed the connection without responding"
+            ));
+        }
+        decode_message(&line)
+    }
+}
+
+/// Writes a message as a single line, including the line break.
+pub fn encode_message<T: Serialize>(message: &T) -> Result<String> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
+
+pub fn decode_message<T: DeserializeOwned>(line: &str) -> Result<T> {
+    serde_json::from_str(line.trim_end())
+        .map_err(|error| problem!("Invalid control message").because(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_requests() {
+        assert_eq!(
+            decode_message::<ControlRequest>("{\"command\":\"reload\"}\n").unwrap(),
+            ControlRequest::Reload
+        );
+        assert_eq!(
+            decode_message::<ControlRequest>("{\"command\":\"cancel\",\"run\":\"1234\"}").unwrap(),
+            ControlRequest::Cancel {
+                run: String::from("1234")
+            }
+        );
+        assert_eq!(
+            decode_message::<ControlRequest>("{\"command\":\"trigger\",\"action\":\"g/a\"}")
+                .unwrap(),
+            ControlRequest::Trigger {
+                action: String::from("g/a"),
+                parameters: TriggerParameters::new()
+            }
+        );
+        assert!(decode_message::<ControlRequest>("{\"command\":\"explode\"}").is_err());
+        assert!(decode_message::<ControlRequest>("reload").is_err());
+    }
+
+    #[test]
+    fn encode_responses() {
+        let response = ControlResponse::Reloaded { enabled_actions: 3 };
+
+        assert_eq!(
+            encode_message(&response).unwrap(),
+            "{\"result\":\"reloaded\",\"enabled_actions\":3}\n"
+        );
+    }
+
+    #[test]
+    fn encode_log_entries() {
+        let entry = LogEntry {
+            time: String::from("2024-11-20T18:00:00Z"),
+            event: RunEvent::Output {
+                stream: OutputStream::Stderr,
+                line: String::from("oops"),
+            },
+        };
+
+        let line = encode_message(&entry).unwrap();
+
+        assert_eq!(
+            line,
+            "{\"time\":\"2024-11-20T18:00:00Z\",\"event\":\"output\",\"stream\":\"stderr\",\"line\":\"oops\"}\n"
+        );
+        assert_eq!(decode_message::<LogEntry>(&line).unwrap(), entry);
+    }
+
+    #[test]
+    fn round_trip() {
+        let response = ControlResponse::Status(ServerStatus {
+            version: String::from("0.1.0"),
+            started_at: String::from("2024-11-20T18:00:00Z"),
+            loaded_at: String::from("2024-11-20T18:00:00Z"),
+            enabled_actions: 2,
+            runs_in_progress: 0,
+        });
+
+        let line = encode_message(&response).unwrap();
+
+        assert_eq!(decode_message::<ControlResponse>(&line).unwrap(), response);
+    }
+}
@@ -41,7 +41,9 @@ where
     O: Write,
 {
     match entry {
-        EnabledEntry::Action(path) => writeln!(output, "action:{}", path.to_string()),
+        EnabledEntry::Action(path) => writeln!(output, "action:{}", path),
+        EnabledEntry::Group(group) => writeln!(output, "group:{}", group),
+        EnabledEntry::Except(path) => writeln!(output, "except:{}", path),
     }
 }
 
@@ -84,7 +86,11 @@ mod tests {
         let result = write_enabled_actions_file(
             &mut output,
             &EnabledList {
-                enabled: vec![EnabledEntry::Action(ActionPath::from_parts("hoshi", "mi"))],
+                enabled: vec![
+                    EnabledEntry::Action(ActionPath::from_parts("hoshi", "mi")),
+                    EnabledEntry::Group(String::from("miko")),
+                    EnabledEntry::Except(ActionPath::from_parts("miko", "to")),
+                ],
             },
         );
 
@@ -96,6 +102,8 @@ mod tests {
                 #: This file is managed by maidono
                 #: please do not modify it manually
                 action:hoshi/mi
+                group:miko
+                except:miko/to
             "}
         );
     }
@@ -1,31 +1,44 @@
 use super::{EnabledEntry, EnabledList};
 use crate::problem;
-use crate::utils::{path, split_in_two, ActionPath, Result};
+use crate::utils::{path, split_in_two, ActionPath, Report, Result};
 use std::fs::read_to_string;
+use std::path::Path;
 
 pub fn load_enabled_actions() -> Result<EnabledList> {
-    let contents = read_to_string(path::ENABLED_ACTIONS_FILE)
+    load_enabled_actions_from(Path::new(path::ENABLED_ACTIONS_FILE))
+}
+
+/// Reads an enabled actions list other than the one of the server.
+pub fn load_enabled_actions_from(file: &Path) -> Result<EnabledList> {
+    let contents = read_to_string(file)
         .map_err(|io_err| problem!("unable to read the enabled actions list").because(io_err))?;
 
-    parse_enabled_actions_file(contents)
+    parse_enabled_actions_file(&file.to_string_lossy(), contents)
 }
 
-fn parse_enabled_actions_file(contents: String) -> Result<EnabledList> {
+fn parse_enabled_actions_file(file: &str, contents: String) -> Result<EnabledList> {
     let mut enabled = Vec::new();
+    let mut report = Report::new();
 
-    for result in contents.lines().map(parse_enabled_action_entry) {
-        match result? {
-            Some(entry) => enabled.push(entry),
-            None => {}
+    for (line_index, line) in contents.lines().enumerate() {
+        match parse_enabled_action_entry(line) {
+            Ok(Some(entry)) => enabled.push(entry),
+            Ok(None) => {}
+            Err(error) => report.add(error.in_file(file.to_owned(), line_index + 1, 1)),
         }
     }
 
-    Ok(EnabledList { enabled })
+    report.wrap(EnabledList { enabled })
 }
 
 fn parse_enabled_action_entry(line: &str) -> Result<Option<EnabledEntry>> {
     match split_in_two(line, ':') {
         ("action", Some(path)) => ActionPath::parse(path).map(|p| Some(EnabledEntry::Action(p))),
+        ("group", Some(group)) if group.is_empty() || group.contains('/') => {
+            Err(problem!("invalid group '{}'", group))
+        }
+        ("group", Some(group)) => Ok(Some(EnabledEntry::Group(group.to_owned()))),
+        ("except", Some(path)) => ActionPath::parse(path).map(|p| Some(EnabledEntry::Except(p))),
         ("#", _) => Ok(None),
         (_, None) => Err(problem!("invalid entry '{}'", line)),
         (other, _) => Err(problem!("invalid scope '{}'", other)),
@@ -35,6 +48,7 @@ fn parse_enabled_action_entry(line: &str) -> Result<Option<EnabledEntry>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::Error;
     use indoc::indoc;
 
     #[test]
@@ -47,12 +61,27 @@ mod tests {
         )
     }
 
+    #[test]
+    fn group_entries() {
+        assert!(matches!(
+            parse_enabled_action_entry("group:my_group"),
+            Ok(Some(EnabledEntry::Group(g))) if g == "my_group"
+        ));
+        assert!(matches!(
+            parse_enabled_action_entry("except:my_group/my_action"),
+            Ok(Some(EnabledEntry::Except(a))) if a == ActionPath::from_parts("my_group", "my_action")
+        ));
+        assert!(parse_enabled_action_entry("group:my_group/my_action").is_err());
+        assert!(parse_enabled_action_entry("group:").is_err());
+        assert!(parse_enabled_action_entry("except:my_group").is_err());
+    }
+
     #[test]
     fn comment_line() {
         let result = parse_enabled_action_entry("#: check this out");
         assert!(result.is_ok());
 
-        assert!(matches!(result.unwrap(), None))
+        assert!(result.unwrap().is_none())
     }
 
     #[test]
@@ -61,13 +90,37 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn locate_invalid_lines() {
+        let result = parse_enabled_actions_file(
+            "enabled",
+            String::from(indoc! {"
+                action:hoshi/mi
+                what:is/this
+                action
+            "}),
+        );
+
+        match result {
+            Err(Error::Multiple(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert!(format!("{:?}", errors[0]).contains("in file enabled, line 2, column 1"));
+                assert!(format!("{:?}", errors[1]).contains("in file enabled, line 3, column 1"));
+            }
+            other => panic!("expected two errors, got {:?}", other),
+        }
+    }
+
     #[test]
     fn read_whole_file() {
-        let result = parse_enabled_actions_file(String::from(indoc! {"
+        let result = parse_enabled_actions_file(
+            "enabled",
+            String::from(indoc! {"
             #: misaka
             action:hoshi/mi
             action:miko/to
-        "}));
+        "}),
+        );
         assert!(result.is_ok());
 
         let ena = result.unwrap();
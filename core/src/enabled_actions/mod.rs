@@ -2,10 +2,12 @@ mod dump;
 mod load;
 
 pub use dump::dump_enabled_actions;
-pub use load::load_enabled_actions;
+pub use load::{load_enabled_actions, load_enabled_actions_from};
 
 use crate::utils::ActionPath;
+use serde::Serialize;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ActionStatus {
     Enabled,
     Disabled,
@@ -21,58 +23,106 @@ impl ActionStatus {
     }
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum EnabledEntry {
     Action(ActionPath),
+    /// All the actions of a group, including the ones added later.
+    Group(String),
+    /// An action left disabled in a group that is enabled.
+    Except(ActionPath),
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Debug, Default)]
 pub struct EnabledList {
     enabled: Vec<EnabledEntry>,
 }
 
 impl EnabledList {
+    pub fn entries(&self) -> std::slice::Iter<'_, EnabledEntry> {
+        self.enabled.iter()
+    }
+
     pub fn is_action_enabled(&self, group: &str, action: &str) -> bool {
-        self.enabled.iter().any(|e| match e {
-            EnabledEntry::Action(a) => a == &ActionPath::from_parts(group, action),
-        })
+        self.is_path_enabled(&ActionPath::from_parts(group, action))
     }
 
     pub fn is_path_enabled(&self, path: &ActionPath) -> bool {
-        self.enabled.iter().any(|e| match e {
+        let included = self.enabled.iter().any(|e| match e {
             EnabledEntry::Action(a) => a == path,
-        })
+            EnabledEntry::Group(g) => g == path.group(),
+            EnabledEntry::Except(_) => false,
+        });
+        included && !self.is_excepted(path)
+    }
+
+    /// Whether the group itself is enabled, rather than some of its actions.
+    pub fn is_group_enabled(&self, group: &str) -> bool {
+        self.enabled
+            .iter()
+            .any(|e| matches!(e, EnabledEntry::Group(g) if g == group))
+    }
+
+    fn is_excepted(&self, path: &ActionPath) -> bool {
+        self.enabled
+            .iter()
+            .any(|e| matches!(e, EnabledEntry::Except(a) if a == path))
     }
 
     pub fn enable_path(&mut self, path: ActionPath) -> bool {
-        let already_enabled = self.enabled.iter().any(|e| match e {
-            EnabledEntry::Action(a) => a == &path,
-        });
-        if !already_enabled {
+        if self.is_path_enabled(&path) {
+            return false;
+        }
+        self.enabled
+            .retain(|e| !matches!(e, EnabledEntry::Except(a) if a == &path));
+        if !self.is_path_enabled(&path) {
             self.enabled.push(EnabledEntry::Action(path));
         }
-        !already_enabled
+        true
     }
 
     pub fn disable_path(&mut self, path: ActionPath) -> bool {
-        let indexes_to_remove: Vec<usize> = self
-            .enabled
-            .iter()
-            .enumerate()
-            .filter_map(|(i, e)| match e {
-                EnabledEntry::Action(a) => {
-                    if a == &path {
-                        Some(i)
-                    } else {
-                        None
-                    }
-                }
-            })
-            .collect();
-        for i in indexes_to_remove.iter().rev() {
-            self.enabled.remove(*i);
+        if !self.is_path_enabled(&path) {
+            return false;
+        }
+        self.enabled
+            .retain(|e| !matches!(e, EnabledEntry::Action(a) if a == &path));
+        if self.is_path_enabled(&path) {
+            self.enabled.push(EnabledEntry::Except(path));
         }
-        !indexes_to_remove.is_empty()
+        true
+    }
+
+    /// Enables all the actions of a group, replacing the entries of its actions.
+    pub fn enable_group(&mut self, group: &str) -> bool {
+        let already_enabled = self.is_group_enabled(group)
+            && !self
+                .enabled
+                .iter()
+                .any(|e| matches!(e, EnabledEntry::Except(a) if a.group() == group));
+
+        self.remove_entries_of(group);
+        self.enabled.push(EnabledEntry::Group(group.to_owned()));
+        !already_enabled
+    }
+
+    /// Disables all the actions of a group, whether they were enabled with the group or one by one.
+    pub fn disable_group(&mut self, group: &str) -> bool {
+        let any_enabled = self.enabled.iter().any(|e| match e {
+            EnabledEntry::Action(a) => a.group() == group,
+            EnabledEntry::Group(g) => g == group,
+            EnabledEntry::Except(_) => false,
+        });
+
+        self.remove_entries_of(group);
+        any_enabled
+    }
+
+    fn remove_entries_of(&mut self, group: &str) {
+        self.enabled.retain(|e| match e {
+            EnabledEntry::Action(a) | EnabledEntry::Except(a) => a.group() != group,
+            EnabledEntry::Group(g) => g != group,
+        });
     }
 }
 
@@ -91,4 +141,64 @@ mod tests {
         assert!(!ena.is_action_enabled("noe", "mi"));
         assert!(!ena.is_action_enabled("miko", "to"));
     }
+
+    #[test]
+    fn are_group_actions_enabled() {
+        let ena = EnabledList {
+            enabled: vec![
+                EnabledEntry::Group(String::from("hoshi")),
+                EnabledEntry::Except(ActionPath::from_parts("hoshi", "no")),
+                EnabledEntry::Action(ActionPath::from_parts("miko", "to")),
+            ],
+        };
+
+        assert!(ena.is_action_enabled("hoshi", "mi"));
+        assert!(ena.is_action_enabled("hoshi", "added-later"));
+        assert!(!ena.is_action_enabled("hoshi", "no"));
+        assert!(ena.is_action_enabled("miko", "to"));
+        assert!(!ena.is_action_enabled("miko", "mi"));
+        assert!(ena.is_group_enabled("hoshi"));
+        assert!(!ena.is_group_enabled("miko"));
+    }
+
+    #[test]
+    fn change_actions_of_enabled_group() {
+        let mut ena = EnabledList {
+            enabled: vec![EnabledEntry::Group(String::from("hoshi"))],
+        };
+        let mi = ActionPath::from_parts("hoshi", "mi");
+
+        assert!(ena.disable_path(mi.clone()));
+        assert!(!ena.is_path_enabled(&mi));
+        assert!(!ena.disable_path(mi.clone()));
+        assert!(matches!(&ena.enabled[1], EnabledEntry::Except(a) if *a == mi));
+
+        assert!(ena.enable_path(mi.clone()));
+        assert!(ena.is_path_enabled(&mi));
+        assert_eq!(ena.enabled.len(), 1);
+    }
+
+    #[test]
+    fn enable_and_disable_groups() {
+        let mut ena = EnabledList {
+            enabled: vec![
+                EnabledEntry::Action(ActionPath::from_parts("hoshi", "mi")),
+                EnabledEntry::Action(ActionPath::from_parts("miko", "to")),
+            ],
+        };
+
+        assert!(ena.enable_group("hoshi"));
+        assert!(!ena.enable_group("hoshi"));
+        assert_eq!(ena.enabled.len(), 2);
+        assert!(ena.is_action_enabled("hoshi", "no"));
+
+        ena.disable_path(ActionPath::from_parts("hoshi", "no"));
+        assert!(ena.enable_group("hoshi"));
+        assert!(ena.is_action_enabled("hoshi", "no"));
+
+        assert!(ena.disable_group("hoshi"));
+        assert!(!ena.disable_group("hoshi"));
+        assert!(!ena.is_action_enabled("hoshi", "mi"));
+        assert!(ena.is_action_enabled("miko", "to"));
+    }
 }
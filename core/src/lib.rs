@@ -1,3 +1,6 @@
 pub mod actions;
+pub mod check;
+pub mod control;
 pub mod enabled_actions;
+pub mod security;
 pub mod utils;
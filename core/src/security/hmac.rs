@@ -0,0 +1,26 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub fn check_hmac_sha256(secret: &str, message: &[u8], digest: &[u8]) -> bool {
+    let mut hmac: Hmac<Sha256> =
+        Hmac::new_from_slice(secret.as_bytes()).expect("HMAC should accept a key of any size");
+    hmac.update(message);
+    hmac.verify_slice(digest).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_known_digest() {
+        let digest =
+            hex::decode("f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8")
+                .unwrap();
+        let message = b"The quick brown fox jumps over the lazy dog";
+
+        assert!(check_hmac_sha256("key", message, &digest));
+        assert!(!check_hmac_sha256("other key", message, &digest));
+        assert!(!check_hmac_sha256("key", b"The quick brown fox", &digest));
+    }
+}
@@ -0,0 +1,150 @@
+use super::{RequestHeaders, Signature};
+use crate::actions::HostRef;
+use crate::problem;
+use crate::utils::Result;
+
+/// Checks the headers that the host always sends with its requests, and tells which one is wrong
+/// if the request can't be coming from it.
+pub fn check_host_headers<H: RequestHeaders + ?Sized>(
+    host_ref: &HostRef,
+    headers: &H,
+) -> Result<()> {
+    match host_ref {
+        HostRef::GitHub => {
+            if !headers
+                .header("User-Agent")
+                .is_some_and(|ua| ua.starts_with("GitHub-Hookshot"))
+            {
+                return Err(problem!(
+                    "The User-Agent header does not start with 'GitHub-Hookshot'"
+                ));
+            }
+            for required in ["X-Github-Delivery", "X-Github-Event"] {
+                if headers.header(required).is_none() {
+                    return Err(problem!("The {} header is missing", required));
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The identifier given by the host to the request, if any.
+pub fn delivery_id<'h, H: RequestHeaders + ?Sized>(
+    host_ref: &HostRef,
+    headers: &'h H,
+) -> Option<&'h str> {
+    match host_ref {
+        HostRef::GitHub => headers.header("X-Github-Delivery"),
+        _ => None,
+    }
+}
+
+/// The kind of event that the request notifies, if the host tells it.
+pub fn event_type<'h, H: RequestHeaders + ?Sized>(
+    host_ref: &HostRef,
+    headers: &'h H,
+) -> Option<&'h str> {
+    match host_ref {
+        HostRef::GitHub => headers.header("X-Github-Event"),
+        _ => None,
+    }
+}
+
+/// The header in which the host sends the signature of the body, if it signs its requests.
+pub fn signature_header(host_ref: &HostRef) -> Option<&'static str> {
+    match host_ref {
+        HostRef::GitHub => Some("X-Hub-Signature-256"),
+        _ => None,
+    }
+}
+
+pub fn extract_signature<H: RequestHeaders + ?Sized>(
+    host_ref: &HostRef,
+    headers: &H,
+) -> Option<Signature> {
+    match host_ref {
+        HostRef::GitHub => headers
+            .header("X-Hub-Signature-256")
+            .and_then(decode_hub256_signature),
+        _ => None,
+    }
+}
+
+fn decode_hub256_signature(signature: &str) -> Option<Signature> {
+    // 7 chars for the prefix + 64 chars for the hash
+    if signature.starts_with("sha256=") && signature.len() == 71 {
+        let mut signature_bytes = [0u8; 32];
+        let decoded = hex::decode_to_slice(&signature[7..], &mut signature_bytes).is_ok();
+        if decoded {
+            Some(Signature::HS256Hex(signature_bytes))
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn check_github_headers() {
+        let valid = headers(&[
+            ("user-agent", "GitHub-Hookshot/abc123"),
+            ("X-GitHub-Delivery", "72d3162e"),
+            ("X-GitHub-Event", "push"),
+        ]);
+        assert!(check_host_headers(&HostRef::GitHub, valid.as_slice()).is_ok());
+        assert_eq!(
+            delivery_id(&HostRef::GitHub, valid.as_slice()),
+            Some("72d3162e")
+        );
+        assert_eq!(event_type(&HostRef::GitHub, valid.as_slice()), Some("push"));
+
+        let missing_event = headers(&[
+            ("User-Agent", "GitHub-Hookshot/abc123"),
+            ("X-GitHub-Delivery", "72d3162e"),
+        ]);
+        assert_eq!(
+            check_host_headers(&HostRef::GitHub, missing_event.as_slice())
+                .unwrap_err()
+                .to_string(),
+            " The X-Github-Event header is missing"
+        );
+
+        let curl = headers(&[("User-Agent", "curl/8.5.0")]);
+        assert!(check_host_headers(&HostRef::GitHub, curl.as_slice()).is_err());
+        assert!(check_host_headers(&HostRef::Any, curl.as_slice()).is_ok());
+        assert_eq!(delivery_id(&HostRef::Any, valid.as_slice()), None);
+    }
+
+    #[test]
+    fn extract_github_signature() {
+        let signed = headers(&[(
+            "X-Hub-Signature-256",
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8",
+        )]);
+        let signature = extract_signature(&HostRef::GitHub, signed.as_slice()).unwrap();
+        assert!(signature.matches("key", b"The quick brown fox jumps over the lazy dog"));
+        assert!(extract_signature(&HostRef::Any, signed.as_slice()).is_none());
+
+        let truncated = headers(&[("X-Hub-Signature-256", "sha256=f7bc83f4")]);
+        assert!(extract_signature(&HostRef::GitHub, truncated.as_slice()).is_none());
+        let not_hex = headers(&[(
+            "X-Hub-Signature-256",
+            "sha256=zzbc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8",
+        )]);
+        assert!(extract_signature(&HostRef::GitHub, not_hex.as_slice()).is_none());
+    }
+}
@@ -0,0 +1,32 @@
+//! The checks made on the requests before they are allowed to trigger an action.
+
+mod hmac;
+mod hosts;
+
+pub use hosts::{check_host_headers, delivery_id, event_type, extract_signature, signature_header};
+
+pub enum Signature {
+    HS256Hex([u8; 32]),
+}
+
+impl Signature {
+    pub fn matches(&self, secret: &str, message: &[u8]) -> bool {
+        match self {
+            Signature::HS256Hex(digest) => hmac::check_hmac_sha256(secret, message, digest),
+        }
+    }
+}
+
+/// The headers of a request, whatever their source is.
+pub trait RequestHeaders {
+    /// The first value of a header. Header names are case-insensitive.
+    fn header(&self, name: &str) -> Option<&str>;
+}
+
+impl RequestHeaders for [(String, String)] {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
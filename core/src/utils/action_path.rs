@@ -1,6 +1,7 @@
 use crate::problem;
 use crate::utils::error::Result;
 use crate::utils::split_in_two;
+use serde::{Serialize, Serializer};
 use std::fmt::Display;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -20,8 +21,27 @@ impl ActionPath {
         }
     }
 
-    pub fn to_string(&self) -> String {
-        format!("{}/{}", self.group_part, self.action_part)
+    /// Resolves a reference found in this action, which can be either absolute (`group/action`)
+    /// or relative to the group of this action (`action`, `./action` or `../group/action`).
+    pub fn resolve(&self, reference: &str) -> Result<ActionPath> {
+        let absolute = if let Some(action) = reference.strip_prefix("./") {
+            if action.contains('/') {
+                return Err(problem!("Invalid relative reference '{}'", reference));
+            }
+            ActionPath::from_parts(self.group_part.as_str(), action)
+        } else if let Some(path) = reference.strip_prefix("../") {
+            ActionPath::parse(path)?
+        } else if reference.contains('/') {
+            ActionPath::parse(reference)?
+        } else {
+            ActionPath::from_parts(self.group_part.as_str(), reference)
+        };
+
+        if absolute.group_part.is_empty() || absolute.action_part.is_empty() {
+            Err(problem!("Invalid reference '{}'", reference))
+        } else {
+            Ok(absolute)
+        }
     }
 
     pub fn from_parts<G: Into<String>, A: Into<String>>(group_part: G, action_part: A) -> Self {
@@ -31,6 +51,10 @@ impl ActionPath {
         }
     }
 
+    pub fn group(&self) -> &str {
+        &self.group_part
+    }
+
     pub fn into_parts(self) -> (String, String) {
         (self.group_part, self.action_part)
     }
@@ -46,6 +70,12 @@ impl Display for ActionPath {
     }
 }
 
+impl Serialize for ActionPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl PartialOrd for ActionPath {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -63,6 +93,8 @@ impl Ord for ActionPath {
     }
 }
 
+/// Selects a group (`group`) or an action (`group/action`). Both parts may contain `*` wildcards,
+/// that match any sequence of characters.
 #[derive(Debug)]
 pub struct ActionPathPattern {
     group_part: String,
@@ -84,12 +116,38 @@ impl ActionPathPattern {
     }
 
     fn test(&self, path: &ActionPath) -> bool {
-        self.group_part == path.group_part
-            && match (self.action_part.as_ref(), &path.action_part) {
-                (None, _) => true,
-                (Some(action_pattern), action_path) => action_pattern == action_path,
+        self.matches_group(&path.group_part)
+            && match &self.action_part {
+                None => true,
+                Some(action_pattern) => wildcard_matches(action_pattern, &path.action_part),
             }
     }
+
+    /// Whether this pattern selects whole groups, rather than some of their actions.
+    pub fn is_group_pattern(&self) -> bool {
+        self.action_part.is_none()
+    }
+
+    /// Tells if some actions of a group may be selected by this pattern.
+    pub fn matches_group(&self, group: &str) -> bool {
+        wildcard_matches(&self.group_part, group)
+    }
+}
+
+fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            // try every position for the rest of the pattern
+            text.char_indices()
+                .map(|(index, _)| index)
+                .chain([text.len()])
+                .any(|index| wildcard_matches(rest, &text[index..]))
+        }
+    }
 }
 
 impl Display for ActionPathPattern {
@@ -114,6 +172,23 @@ mod tests {
         assert_eq!(result.as_ref().unwrap().action_part, "my_action");
     }
 
+    #[test]
+    fn resolve_references() {
+        let base = ActionPath::from_parts("my_group", "my_action");
+
+        let resolve = |reference| base.resolve(reference).unwrap();
+        assert_eq!(resolve("other"), ActionPath::from_parts("my_group", "other"));
+        assert_eq!(resolve("./other"), ActionPath::from_parts("my_group", "other"));
+        assert_eq!(resolve("../group/other"), ActionPath::from_parts("group", "other"));
+        assert_eq!(resolve("group/other"), ActionPath::from_parts("group", "other"));
+
+        assert!(base.resolve("").is_err());
+        assert!(base.resolve("./").is_err());
+        assert!(base.resolve("./group/other").is_err());
+        assert!(base.resolve("../other").is_err());
+        assert!(base.resolve("group/").is_err());
+    }
+
     #[test]
     fn parse_fixed_group_pattern() {
         let result = ActionPathPattern::parse("my_group");
@@ -134,4 +209,23 @@ mod tests {
             Some(String::from("my_action"))
         );
     }
+
+    #[test]
+    fn match_wildcard_patterns() {
+        let matches = |pattern, path| {
+            ActionPath::parse(path)
+                .unwrap()
+                .matches(&ActionPathPattern::parse(pattern).unwrap())
+        };
+
+        assert!(matches("website", "website/deploy"));
+        assert!(!matches("website", "websites/deploy"));
+        assert!(matches("web*", "website/deploy"));
+        assert!(matches("*", "website/deploy"));
+        assert!(matches("*/deploy", "website/deploy"));
+        assert!(matches("website/*-prod", "website/deploy-prod"));
+        assert!(!matches("website/*-prod", "website/deploy-staging"));
+        assert!(matches("*site/d*y", "website/deploy"));
+        assert!(!matches("site*", "website/deploy"));
+    }
 }
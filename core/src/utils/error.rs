@@ -1,4 +1,5 @@
 use super::Location;
+use serde::{Serialize, Serializer};
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -19,7 +20,7 @@ impl Error {
     }
 
     pub fn at(self, location: Location) -> Self {
-        Self::At(Box::new(self), location.into())
+        Self::At(Box::new(self), location)
     }
 
     pub fn in_file(self, file: String, line: usize, column: usize) -> Self {
@@ -75,6 +76,64 @@ impl Error {
     }
 }
 
+/// The shape of an error in machine-readable documents: a message with an optional location, and
+/// the errors that caused it.
+#[derive(Serialize)]
+struct StructuredError<'a> {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<&'a Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    causes: Vec<StructuredError<'a>>,
+}
+
+impl Error {
+    fn structured(&self) -> StructuredError<'_> {
+        match self {
+            Error::StaticMessage(msg) => StructuredError::new(msg.to_string()),
+            Error::DynamicMessage(msg) => StructuredError::new(msg.clone()),
+            Error::Because(err, cause) => {
+                let mut structured = err.structured();
+                match cause.as_ref() {
+                    Error::Multiple(causes) => structured
+                        .causes
+                        .extend(causes.iter().map(Error::structured)),
+                    cause => structured.causes.push(cause.structured()),
+                }
+                structured
+            }
+            Error::At(err, location) => {
+                let mut structured = err.structured();
+                structured.location.get_or_insert(location);
+                structured
+            }
+            Error::Multiple(errs) => {
+                let mut structured = StructuredError::new(format!("{} errors", errs.len()));
+                structured.causes = errs.iter().map(Error::structured).collect();
+                structured
+            }
+        }
+    }
+}
+
+impl StructuredError<'_> {
+    fn new(message: String) -> Self {
+        Self {
+            message,
+            location: None,
+            causes: Vec::new(),
+        }
+    }
+}
+
+impl Serialize for Error {
+    /// Serializes the error as an object with a `message`, and optionally a `location` and a list
+    /// of `causes` that are errors too.
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.structured().serialize(serializer)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
         Self::DynamicMessage(value.to_string())
@@ -87,6 +146,12 @@ impl From<serde_yaml::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Self::DynamicMessage(value.to_string())
+    }
+}
+
 impl From<&str> for Error {
     fn from(value: &str) -> Self {
         Self::DynamicMessage(value.to_owned())
@@ -125,6 +190,14 @@ impl MinimalErrorMessageBuilder {
             String::new()
         }
     }
+
+    fn push<D: Display>(&mut self, text: D) {
+        if self.new_line && !self.string.is_empty() {
+            self.string.push('\n');
+        }
+        let line = format!(" {}{}", self.pad(), text);
+        self.string.push_str(&line);
+    }
 }
 
 impl ErrorPrinter for MinimalErrorMessageBuilder {
@@ -139,13 +212,13 @@ impl ErrorPrinter for MinimalErrorMessageBuilder {
     }
 
     fn print_error<D: Display>(&mut self, text: D) -> &mut Self {
-        eprintln!(" {}{}", self.pad(), text);
+        self.push(text);
         self.new_line = true;
         self
     }
 
     fn print_error_inline<D: Display>(&mut self, text: D) -> &mut Self {
-        eprint!(" {}{}", self.pad(), text);
+        self.push(text);
         self.new_line = false;
         self
     }
@@ -158,3 +231,41 @@ impl Debug for Error {
         fmt.write_str(&memb.string)
     }
 }
+
+impl Display for Error {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        Debug::fmt(self, fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem;
+
+    #[test]
+    fn serialize_structured_errors() {
+        let error = problem!("Invalid group 'website'")
+            .because(
+                problem!("Unknown field 'cmd'")
+                    .in_file(String::from("website.yml"), 3, 5)
+                    .and(Error::from("Missing field 'on'")),
+            )
+            .at(Location::new(String::from("enabled"), 1, 1));
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "message": "Invalid group 'website'",
+                "location": { "file": "enabled", "line": 1, "column": 1 },
+                "causes": [
+                    {
+                        "message": "Unknown field 'cmd'",
+                        "location": { "file": "website.yml", "line": 3, "column": 5 },
+                    },
+                    { "message": "Missing field 'on'" },
+                ],
+            })
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use serde::Serialize;
 use std::fmt::Display;
 
+#[derive(Debug, Clone, Serialize)]
 pub struct Location {
     file: String,
     line: usize,
@@ -8,7 +8,7 @@ macro_rules! problem {
         $crate::utils::Error::from($e)
     };
 
-    ($f: expr, $($args:expr)*) => {
-    	$crate::utils::Error::DynamicMessage(format!($f, $($args)*))
+    ($f: expr, $($args:expr),* $(,)?) => {
+    	$crate::utils::Error::DynamicMessage(format!($f, $($args),*))
     }
 }
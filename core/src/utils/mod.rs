@@ -10,7 +10,24 @@ pub use error::{Error, ErrorPrinter, Result};
 pub use location::Location;
 pub(crate) use report::Report;
 
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
 pub(crate) fn split_in_two(string: &str, c: char) -> (&str, Option<&str>) {
     let mut parts = string.splitn(2, c);
     (parts.next().unwrap(), parts.next())
 }
+
+/// Writes a file that must not exist yet, created with at most the given mode. Unlike `fs::write`,
+/// it never follows a symlink that someone else put in its place.
+pub fn write_new_file(file: &Path, contents: &str, mode: u32) -> Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(file)
+        .and_then(|mut opened| opened.write_all(contents.as_bytes()))
+        .map_err(|err| crate::problem!("Unable to write {}", file.display()).because(err))
+}
@@ -6,3 +6,16 @@ pub const SERVER_CONFIG_FILE: &str = "/etc/maidono/config.toml";
 
 pub const WEB_APP_INDEX: &str = "/usr/share/maidono/web/index.html";
 pub const WEB_APP_ASSETS: &str = "/usr/share/maidono/web/assets";
+
+pub const CONTROL_SOCKET: &str = "/var/maidono/maidono.sock";
+
+/// The working directory of the server.
+pub const RUNTIME_DIR: &str = "/var/maidono";
+
+/// Where `maidctl start` writes the PID of the server and its output when there is no service
+/// manager.
+pub const SERVER_PID_FILE: &str = "/var/maidono/maidono.pid";
+pub const SERVER_LOG_FILE: &str = "/var/maidono/maidono.log";
+
+/// The directory containing the `actions` directory and the `enabled` file.
+pub const CONFIG_DIR: &str = "/etc/maidono";
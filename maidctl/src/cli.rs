@@ -1,5 +1,7 @@
+use crate::output::OutputFormat;
 use clap::{Parser, Subcommand};
 use clio::Input;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "maidctl")]
@@ -8,16 +10,28 @@ use clio::Input;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// The format of the output of list, show, enable, disable, check and runs
+    #[arg(short, long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// List the available actions
     List {
+        /// Only list the matching actions, like "website", "website/deploy-*" or "*/deploy"
+        pattern: Option<String>,
+        /// Only list the enabled actions
         #[arg(long)]
         enabled: bool,
+        /// Only list the disabled actions
         #[arg(long)]
         disabled: bool,
+        /// Only list the actions triggered from this origin: github, * or a custom host
+        #[arg(long)]
+        from: Option<String>,
+        /// Only list the problems of the groups
         #[arg(long)]
         invalid: bool,
     },
@@ -25,12 +39,54 @@ pub enum Commands {
     /// Show the configuration of a group
     Show { group: String },
 
+    /// Edit a group file safely, and reload the server
+    Edit { group: String },
+
     /// Enable an action or a group
     Enable { actions: Vec<String> },
 
     /// Disable an action or a group
     Disable { actions: Vec<String> },
 
+    /// Add an action to a group, asking for what is not given
+    New {
+        /// The action to create, as <group>/<name>
+        path: Option<String>,
+        /// The trigger of the action, like "POST /deploy/<env>"
+        #[arg(long)]
+        on: Option<String>,
+        /// Where the requests come from: github, or * for anyone
+        #[arg(long)]
+        from: Option<String>,
+        /// A command to run (can be repeated)
+        #[arg(long)]
+        run: Vec<String>,
+        /// Generate a random secret
+        #[arg(long)]
+        secret: bool,
+        /// Enable the action once it is created
+        #[arg(long)]
+        enable: bool,
+    },
+
+    /// Show what runs when an action is triggered, following its before and after fields
+    Graph {
+        /// Only show the actions matching this pattern, and the actions they run
+        pattern: Option<String>,
+        /// Print the graph in the DOT language of Graphviz
+        #[arg(long)]
+        dot: bool,
+    },
+
+    /// Check the groups and the enabled actions of a config directory
+    Check {
+        /// A directory containing an `actions` directory and an `enabled` file [default: /etc/maidono]
+        dir: Option<PathBuf>,
+        /// Fail if there are warnings
+        #[arg(long)]
+        warnings_as_errors: bool,
+    },
+
     /// Trigger an action for testing
     Test {
         name_or_url: String,
@@ -40,18 +96,74 @@ pub enum Commands {
         payload_file: Option<Input>,
     },
 
-    /// Show the status of the service
+    /// Tell which actions a request would trigger, or why it would be rejected, without sending it
+    Explain {
+        /// The method of the request, like POST
+        method: String,
+        /// The URL of the request, or only its path
+        url: String,
+        /// A header of the request, like "X-GitHub-Event: push" (can be repeated)
+        #[arg(short = 'H', long = "header")]
+        headers: Vec<String>,
+        /// A file containing the body of the request
+        #[arg(short = 'F', long)]
+        payload_file: Option<PathBuf>,
+    },
+
+    /// Print the output of the last run, or of a given run
+    Logs {
+        /// Print the last run of the actions matching this pattern
+        pattern: Option<String>,
+        /// The ID of the run to print
+        #[arg(long)]
+        run: Option<String>,
+        /// Wait for the run to finish, printing its output as it comes
+        #[arg(short, long)]
+        follow: bool,
+        /// Print the last run that failed
+        #[arg(long)]
+        failed: bool,
+    },
+
+    /// List the recent runs, or show one of them
+    #[command(args_conflicts_with_subcommands = true)]
+    Runs {
+        #[command(subcommand)]
+        command: Option<RunsCommands>,
+        /// Only list the runs of the actions matching this pattern
+        pattern: Option<String>,
+        /// Only list the runs with this status
+        #[arg(long, value_parser = ["running", "succeeded", "failed", "timeout", "cancelled"])]
+        status: Option<String>,
+        /// Only list the runs started since this time: a duration like 30m, 2h or 7d, or a date
+        #[arg(long)]
+        since: Option<String>,
+        /// Only list the runs started before this time: a duration like 30m, 2h or 7d, or a date
+        #[arg(long)]
+        until: Option<String>,
+        /// List at most this many runs
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+
+    /// Show the status of the server
     Status,
 
-    /// Reload the service
+    /// Reload the actions of the running server
     Reload,
 
-    /// Start the service
+    /// Start the server with the service manager (systemd, OpenRC or runit), or in the background
     Start,
 
-    /// Stop the service
+    /// Stop the server with the service manager
     Stop,
 
-    /// Restart the service
+    /// Restart the server with the service manager
     Restart,
 }
+
+#[derive(Subcommand, Debug)]
+pub enum RunsCommands {
+    /// Show the result of each action and command of a run
+    Show { id: String },
+}
@@ -0,0 +1,69 @@
+use crate::output::OutputFormat;
+use crate::printer::Printer;
+use maidono_core::check::check_config_dir;
+use maidono_core::utils::path::CONFIG_DIR;
+use maidono_core::utils::{Error, ErrorPrinter};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+#[derive(Serialize)]
+struct CheckDocument<'a> {
+    dir: &'a Path,
+    valid: bool,
+    errors: Vec<Error>,
+    warnings: Vec<Error>,
+}
+
+pub fn check(dir: Option<PathBuf>, warnings_as_errors: bool, format: OutputFormat) {
+    let dir = dir.unwrap_or_else(|| PathBuf::from(CONFIG_DIR));
+
+    let report = check_config_dir(&dir);
+    let failed = !report.errors.is_empty() || (warnings_as_errors && !report.warnings.is_empty());
+    let document = CheckDocument {
+        dir: &dir,
+        valid: !failed,
+        errors: report.errors,
+        warnings: report.warnings,
+    };
+    format.print(&document, |printer, document| {
+        print_report(printer, document, failed)
+    });
+
+    if failed {
+        exit(1);
+    }
+}
+
+fn print_report(printer: &mut Printer, document: &CheckDocument, failed: bool) {
+    let error_count = document.errors.len();
+    let warning_count = document.warnings.len();
+
+    for error in &document.errors {
+        printer.print_detailed_error(error);
+    }
+    for warning in &document.warnings {
+        printer.print_detailed_warning(warning);
+    }
+
+    if error_count == 0 && warning_count == 0 {
+        printer
+            .print_text("●".green())
+            .print_em_text(document.dir.display())
+            .println_text("is valid");
+        return;
+    }
+
+    let summary = format!(
+        "Found {} error(s) and {} warning(s) in {}",
+        error_count,
+        warning_count,
+        document.dir.display()
+    );
+    if failed {
+        printer.print_error(summary);
+    } else {
+        printer.print_warning(summary);
+    }
+}
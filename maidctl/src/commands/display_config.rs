@@ -1,166 +1,453 @@
-use maidono_core::actions::{read_group_by_name, try_read_all_groups, Action, Group};
+use maidono_core::actions::{
+    read_group_by_name, try_read_all_groups, Action, Commands, Dependencies, ExecutionPlan, Group,
+    HostRef, Trigger,
+};
 use maidono_core::enabled_actions::{load_enabled_actions, ActionStatus, EnabledList};
-use maidono_core::utils::ErrorPrinter;
+use maidono_core::problem;
+use maidono_core::utils::{ActionPath, ActionPathPattern, Error, ErrorPrinter, Result};
 use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::process::exit;
 
+use crate::output::OutputFormat;
 use crate::printer::{Printer, SensitiveStr};
 
-pub fn list(_with_status: Option<ActionStatus>, invalid_only: bool) {
-    let mut printer = Printer::new();
+/// Restricts the actions shown by `maidctl list`.
+pub struct ListFilter {
+    pub status: Option<ActionStatus>,
+    pub pattern: Option<String>,
+    pub from: Option<String>,
+}
+
+struct ParsedListFilter<'a> {
+    status: Option<ActionStatus>,
+    pattern: Option<ActionPathPattern>,
+    origin: Option<HostRef>,
+    enabled: Option<&'a EnabledList>,
+}
+
+impl ParsedListFilter<'_> {
+    fn is_active(&self) -> bool {
+        self.status.is_some() || self.pattern.is_some() || self.origin.is_some()
+    }
+
+    fn accepts_group(&self, name: &str) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.matches_group(name))
+    }
+
+    fn accepts_action(&self, path: &ActionPath, action: &Action) -> bool {
+        let pattern_matches = self
+            .pattern
+            .as_ref()
+            .is_none_or(|pattern| path.matches(pattern));
+        let origin_matches = self
+            .origin
+            .as_ref()
+            .is_none_or(|origin| action.origin() == origin);
+        let status_matches = match self.status {
+            None => true,
+            // the status is unknown if the enabled list couldn't be read
+            Some(status) => self.enabled.is_some_and(|enabled| {
+                enabled.is_path_enabled(path) == (status == ActionStatus::Enabled)
+            }),
+        };
+        pattern_matches && origin_matches && status_matches
+    }
+}
+
+#[derive(Serialize)]
+struct ListDocument<'a> {
+    groups: Vec<ListedGroup<'a>>,
+    errors: Vec<Error>,
+    warnings: Vec<Error>,
+}
+
+#[derive(Serialize)]
+struct ListedGroup<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a Error>,
+    actions: Vec<ListedAction<'a>>,
+}
+
+#[derive(Serialize)]
+struct ListedAction<'a> {
+    name: &'a str,
+    on: &'a Trigger,
+    from: &'a HostRef,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+}
+
+pub fn list(filter: ListFilter, invalid_only: bool, format: OutputFormat) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let pattern = match filter
+        .pattern
+        .as_deref()
+        .map(ActionPathPattern::parse)
+        .transpose()
+    {
+        Ok(pattern) => pattern,
+        Err(error) => {
+            let document = ListDocument {
+                groups: Vec::new(),
+                errors: vec![error],
+                warnings,
+            };
+            format.print(&document, print_list);
+            exit(1);
+        }
+    };
 
     let enabled = match load_enabled_actions() {
         Ok(el) => Some(el),
         Err(err) => {
-            printer.print_detailed_error(err);
+            errors.push(err);
             None
         }
     };
+    let filter = ParsedListFilter {
+        status: filter.status,
+        pattern,
+        origin: filter.from.as_deref().map(HostRef::parse),
+        enabled: enabled.as_ref(),
+    };
 
-    match try_read_all_groups() {
-        Ok(groups) => {
-            for (name, result) in groups {
-                match result {
-                    Ok(group) => {
-                        if !invalid_only {
-                            list_group(&mut printer, enabled.as_ref(), &name, group);
-                        }
-                    }
-                    Err(error) => {
-                        printer
-                            .print_text(name)
-                            .print_very_compact_error(error)
-                            .println();
-                    }
-                }
-            }
-        }
+    let groups: Vec<(String, Result<Group>)> = match try_read_all_groups() {
+        Ok(groups) => groups.into_iter().collect(),
         Err(err) => {
-            printer.print_detailed_error(err);
+            errors.push(err);
+            Vec::new()
+        }
+    };
+    let valid_groups: Vec<(&str, &Group)> = groups
+        .iter()
+        .filter_map(|(name, result)| result.as_ref().ok().map(|group| (name.as_str(), group)))
+        .collect();
+
+    if let Err(error) = Dependencies::from_groups(valid_groups.iter().copied()).plans() {
+        errors.push(error);
+    }
+    for (name, group) in &valid_groups {
+        for (action_name, action) in group.enumerate_actions() {
+            warnings.extend(reserved_path_warning(
+                &ActionPath::from_parts(*name, action_name),
+                action,
+            ));
         }
     }
-}
 
-fn list_group(printer: &mut Printer, enabled: Option<&EnabledList>, name: &str, group: Group) {
-    printer.print_text(name);
-    if group.action_count() == 0 {
-        printer.print_text("(empty)");
+    let listed = groups
+        .iter()
+        .filter(|(name, _)| filter.accepts_group(name))
+        .filter_map(|(name, result)| match result {
+            Ok(group) if !invalid_only => list_group(&filter, name, group),
+            Ok(_) => None,
+            Err(error) => Some(ListedGroup {
+                name,
+                error: Some(error),
+                actions: Vec::new(),
+            }),
+        })
+        .collect();
+
+    let document = ListDocument {
+        groups: listed,
+        errors,
+        warnings,
+    };
+    format.print(&document, print_list);
+    if !document.errors.is_empty() {
+        exit(1);
     }
-    printer.println().indent();
+}
+
+fn list_group<'a>(
+    filter: &ParsedListFilter,
+    name: &'a str,
+    group: &'a Group,
+) -> Option<ListedGroup<'a>> {
+    let actions: Vec<ListedAction> = group
+        .enumerate_actions()
+        .filter_map(|(action_name, action)| {
+            let path = ActionPath::from_parts(name, action_name);
+            filter.accepts_action(&path, action).then(|| ListedAction {
+                name: action_name,
+                on: action.trigger(),
+                from: action.origin(),
+                enabled: filter.enabled.map(|enabled| enabled.is_path_enabled(&path)),
+            })
+        })
+        .collect();
 
-    for action_name in group.enumerate_action_names() {
-        list_action(printer, enabled, name, action_name);
+    if actions.is_empty() && filter.is_active() {
+        None
+    } else {
+        Some(ListedGroup {
+            name,
+            error: None,
+            actions,
+        })
     }
 }
 
-pub fn list_action(printer: &mut Printer, enabled: Option<&EnabledList>, group: &str, name: &str) {
-    let (is_enabled, is_disabled) = if let Some(enabled_list) = enabled {
-        if enabled_list.is_action_enabled(group, name) {
-            (true, false)
-        } else {
-            (false, true)
+fn print_list(printer: &mut Printer, document: &ListDocument) {
+    for group in &document.groups {
+        printer.print_text(group.name);
+        if let Some(error) = group.error {
+            printer.print_very_compact_error(error).println();
+            continue;
         }
-    } else {
-        (false, false)
-    };
+        if group.actions.is_empty() {
+            printer.print_text("(empty)");
+        }
+        printer.println().indent();
 
-    if is_enabled {
-        printer.print_text("●".green());
-    }
-    if is_disabled {
-        printer.print_text("○");
+        for action in &group.actions {
+            print_status(printer, action.enabled);
+            printer
+                .print_text(action.name)
+                .print_text(action.on.dimmed())
+                .println();
+        }
+        printer.unindent();
     }
-    printer.print_text(name).println();
+    print_problems(printer, &document.errors, &document.warnings);
 }
 
-pub fn show(name: String) {
-    let mut printer = Printer::new();
+#[derive(Serialize)]
+struct ShowDocument<'a> {
+    group: &'a str,
+    actions: Vec<ShownAction<'a>>,
+    errors: Vec<Error>,
+}
+
+#[derive(Serialize)]
+struct ShownAction<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+    on: &'a Trigger,
+    from: &'a HostRef,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<SensitiveStr<'a>>,
+    before: Vec<String>,
+    after: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan: Option<ExecutionPlan>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan_error: Option<Error>,
+    run: &'a Commands,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<Error>,
+}
+
+pub fn show(name: String, format: OutputFormat) {
+    let mut errors = Vec::new();
 
     let enabled = match load_enabled_actions() {
         Ok(el) => Some(el),
         Err(err) => {
-            printer.print_detailed_error(err);
+            errors.push(err);
             None
         }
     };
 
-    match read_group_by_name(&name) {
-        Ok(group) => {
-            printer.print_em_text(&name);
-            if group.action_count() == 0 {
-                printer.print_text("(empty)");
-            }
-            printer.println().indent();
+    let group = match read_group_by_name(&name) {
+        Ok(group) => Some(group),
+        Err(err) => {
+            errors.push(err);
+            None
+        }
+    };
+    let other_groups = match &group {
+        Some(_) => read_other_valid_groups(&mut errors, &name),
+        None => Vec::new(),
+    };
 
-            for (action_name, action) in group.enumerate_actions() {
-                show_action(&mut printer, enabled.as_ref(), &name, action_name, action);
-            }
+    let mut actions = Vec::new();
+    if let Some(group) = &group {
+        let dependencies = Dependencies::from_groups(
+            other_groups
+                .iter()
+                .map(|(name, group)| (name.as_str(), group))
+                .chain([(name.as_str(), group)]),
+        );
+        for (action_name, action) in group.enumerate_actions() {
+            let path = ActionPath::from_parts(&name, action_name);
+            let plan = dependencies.plan(&path);
+            actions.push(show_action(
+                enabled.as_ref(),
+                &path,
+                action_name,
+                action,
+                plan,
+            ));
         }
+    }
+
+    let document = ShowDocument {
+        group: &name,
+        actions,
+        errors,
+    };
+    format.print(&document, |printer, document| {
+        if group.is_some() {
+            print_group(printer, document);
+        }
+        print_problems(printer, &document.errors, &[]);
+    });
+    if !document.errors.is_empty() {
+        exit(1);
+    }
+}
+
+fn read_other_valid_groups(errors: &mut Vec<Error>, name: &str) -> Vec<(String, Group)> {
+    match try_read_all_groups() {
+        Ok(groups) => groups
+            .into_iter()
+            .filter(|(group_name, _)| group_name != name)
+            .filter_map(|(group_name, result)| result.ok().map(|group| (group_name, group)))
+            .collect(),
         Err(err) => {
-            printer.print_detailed_error(err);
+            errors.push(err);
+            Vec::new()
         }
     }
 }
 
-pub fn show_action(
-    printer: &mut Printer,
+fn show_action<'a>(
     enabled: Option<&EnabledList>,
-    group: &str,
-    name: &str,
-    action: &Action,
-) {
-    let (is_enabled, is_disabled) = if let Some(enabled_list) = enabled {
-        if enabled_list.is_action_enabled(group, name) {
-            (true, false)
-        } else {
-            (false, true)
-        }
-    } else {
-        (false, false)
+    path: &ActionPath,
+    name: &'a str,
+    action: &'a Action,
+    plan: Result<ExecutionPlan>,
+) -> ShownAction<'a> {
+    let (plan, plan_error) = match plan {
+        Ok(plan) => (Some(plan), None),
+        Err(error) => (None, Some(error)),
     };
-
-    if is_enabled {
-        printer.print_text("●".green());
+    ShownAction {
+        name,
+        enabled: enabled.map(|enabled_list| enabled_list.is_path_enabled(path)),
+        on: action.trigger(),
+        from: action.origin(),
+        secret: action.secret().map(SensitiveStr::from),
+        before: normalize_refs(path, action.before()).collect(),
+        after: normalize_refs(path, action.after()).collect(),
+        plan,
+        plan_error,
+        run: action.action(),
+        warnings: reserved_path_warning(path, action).into_iter().collect(),
     }
-    if is_disabled {
-        printer.print_text("○");
+}
+
+fn print_group(printer: &mut Printer, document: &ShowDocument) {
+    printer.print_em_text(document.group);
+    if document.actions.is_empty() {
+        printer.print_text("(empty)");
     }
-    printer.print_em_text(name);
+    printer.println().indent();
 
-    if is_enabled {
-        printer.print_text("(enabled)");
+    for action in &document.actions {
+        print_action(printer, action);
     }
-    if is_disabled {
-        printer.print_text("(disabled)");
+}
+
+fn print_action(printer: &mut Printer, action: &ShownAction) {
+    print_status(printer, action.enabled);
+    printer.print_em_text(action.name);
+
+    match action.enabled {
+        Some(true) => {
+            printer.print_text("(enabled)");
+        }
+        Some(false) => {
+            printer.print_text("(disabled)");
+        }
+        None => {}
     }
     printer.println().indent();
 
-    printer
-        .print_text("trigger:")
-        .println_text(action.trigger())
-        .print_text("origin:")
-        .println_text(action.origin());
-    if let Some(secret) = action.secret() {
-        printer
-            .print_text("secret:")
-            .println_text(SensitiveStr::from(secret));
+    printer.print_text("trigger:").println_text(action.on);
+    for warning in &action.warnings {
+        printer.print_detailed_warning(warning);
     }
-    if !action.before().is_empty() {
-        printer.print_text("before:").println_list(action.before());
+    printer.print_text("origin:").println_text(action.from);
+    if let Some(secret) = &action.secret {
+        printer.print_text("secret:").println_text(secret);
     }
-    if !action.after().is_empty() {
-        printer.print_text("after:").println_list(action.after());
+    if !action.before.is_empty() {
+        printer.print_text("before:").println_list(&action.before);
+    }
+    if !action.after.is_empty() {
+        printer.print_text("after:").println_list(&action.after);
+    }
+    if let Some(plan) = &action.plan {
+        if plan.steps().len() > 1 {
+            printer.print_text("plan:").println_list(plan.steps());
+        }
     }
-    if action.action().has_multiple_commands() {
+    if let Some(error) = &action.plan_error {
+        printer
+            .print_text("plan:")
+            .print_very_compact_error(error)
+            .println();
+    }
+    if action.run.has_multiple_commands() {
         printer
             .println_text("command:")
             .indent()
-            .print_multiline(action.action())
+            .print_multiline(action.run)
             .unindent();
     } else {
-        printer
-            .print_text("command:")
-            .print_multiline(action.action());
+        printer.print_text("command:").print_multiline(action.run);
     }
 
     printer.unindent().println();
 }
+
+fn print_status(printer: &mut Printer, enabled: Option<bool>) {
+    match enabled {
+        Some(true) => {
+            printer.print_text("●".green());
+        }
+        Some(false) => {
+            printer.print_text("○");
+        }
+        None => {}
+    }
+}
+
+fn print_problems(printer: &mut Printer, errors: &[Error], warnings: &[Error]) {
+    for error in errors {
+        printer.print_detailed_error(error);
+    }
+    for warning in warnings {
+        printer.print_detailed_warning(warning);
+    }
+}
+
+fn normalize_refs<'a>(
+    path: &'a ActionPath,
+    refs: &'a [String],
+) -> impl Iterator<Item = String> + 'a {
+    refs.iter().map(|reference| match path.resolve(reference) {
+        Ok(resolved) => resolved.to_string(),
+        Err(_) => reference.clone(),
+    })
+}
+
+fn reserved_path_warning(path: &ActionPath, action: &Action) -> Option<Error> {
+    action.trigger().reserved_path().map(|reserved| {
+        problem!(
+            "The trigger of '{}' uses the path '/{}', which is reserved by the server",
+            path,
+            reserved
+        )
+    })
+}
@@ -0,0 +1,117 @@
+use crate::commands::prompt::ask;
+use crate::commands::reload;
+use crate::printer::Printer;
+use maidono_core::actions::{find_group_file, new_group_file, parse_group, replace_group_file};
+use maidono_core::problem;
+use maidono_core::utils::{write_new_file, Result};
+use owo_colors::OwoColorize;
+use std::env;
+use std::fs::{self, read_to_string};
+use std::path::Path;
+use std::process::Command;
+
+pub fn edit(group: String) {
+    let mut printer = Printer::new();
+
+    match edit_group(&mut printer, &group) {
+        Ok(true) => {
+            printer
+                .print_text("●".green())
+                .print_em_text(&group)
+                .println_text("was saved");
+            reload();
+        }
+        Ok(false) => {}
+        Err(err) => {
+            printer.print_detailed_error(&err);
+        }
+    }
+}
+
+/// Edits a copy of the group file until it is valid, then replaces the original. Returns whether
+/// the group was changed.
+fn edit_group(printer: &mut Printer, group: &str) -> Result<bool> {
+    // the name is also used for the copy, so it is checked even if the group exists
+    let new_file = new_group_file(group)?;
+    let (file, original) = match find_group_file(group)? {
+        Some(file) => {
+            let original = read_to_string(&file)?;
+            (file, original)
+        }
+        None => {
+            printer
+                .print_text("group")
+                .print_em_text(group)
+                .println_text("does not exist yet, it will be created");
+            (new_file, String::new())
+        }
+    };
+
+    // the copy is edited outside of the actions directory, where any file is read as a group
+    let copy = env::temp_dir().join(format!("maidono-{}-{}.yml", group, std::process::id()));
+    // only the current user can read the copy, which may contain secrets
+    write_new_file(&copy, &original, 0o600)?;
+    let result = edit_until_valid(printer, &file, &copy, &original);
+    let _ = fs::remove_file(&copy);
+
+    match result? {
+        Some(edited) => {
+            replace_group_file(&file, &edited)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Returns the new source of the group, or nothing if it was not changed or the changes were
+/// discarded.
+fn edit_until_valid(
+    printer: &mut Printer,
+    file: &Path,
+    copy: &Path,
+    original: &str,
+) -> Result<Option<String>> {
+    loop {
+        run_editor(copy)?;
+        let edited = read_to_string(copy)?;
+
+        if edited == original {
+            printer.println_text("No changes were made.");
+            return Ok(None);
+        }
+
+        // errors are located in the original file, since the copy is temporary
+        match parse_group(&file.to_string_lossy(), &edited) {
+            Ok(_) => return Ok(Some(edited)),
+            Err(err) => {
+                printer.print_detailed_error(&err);
+                let answer = ask("What now? (e)dit again, or (d)iscard the changes [e]")?;
+                if answer.to_ascii_lowercase().starts_with('d') {
+                    printer.println_text("The changes were discarded.");
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+fn run_editor(file: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+
+    // the editor may come with arguments, like "code --wait"
+    let status = Command::new("/bin/sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(file)
+        .status()
+        .map_err(|err| problem!("Unable to run the editor '{}'", editor).because(err))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(problem!("The editor exited with {}", status))
+    }
+}
@@ -1,121 +1,219 @@
+use crate::output::OutputFormat;
 use crate::Printer;
 use maidono_core::actions::{try_read_all_groups, Group};
 use maidono_core::enabled_actions::{dump_enabled_actions, load_enabled_actions, EnabledList};
-use maidono_core::utils::{ActionPath, ActionPathPattern, ErrorPrinter, Result};
+use maidono_core::problem;
+use maidono_core::utils::{ActionPath, ActionPathPattern, Error, Result};
 use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::process::exit;
 
-pub fn enable(actions: Vec<String>) {
-    let mut printer = Printer::new();
+/// What `maidctl enable` or `maidctl disable` did.
+#[derive(Serialize, Default)]
+struct ChangeDocument {
+    groups: Vec<ChangedGroup>,
+    actions: Vec<ChangedAction>,
+    unmatched_patterns: Vec<String>,
+    errors: Vec<Error>,
+}
 
-    match for_each_pattern(actions, &mut printer, enable_pattern) {
-        Ok(list) => match dump_enabled_actions(&list) {
-            Ok(()) => {}
-            Err(err) => {
-                printer.print_detailed_error(err);
-            }
+/// A group enabled or disabled as a whole, including the actions added to it later.
+#[derive(Serialize)]
+struct ChangedGroup {
+    group: String,
+    enabled: bool,
+    changed: bool,
+}
+
+#[derive(Serialize)]
+struct ChangedAction {
+    path: ActionPath,
+    enabled: bool,
+    /// False if the action already had this status.
+    changed: bool,
+}
+
+pub fn enable(actions: Vec<String>, format: OutputFormat) {
+    change_status(actions, format, true);
+}
+
+pub fn disable(actions: Vec<String>, format: OutputFormat) {
+    change_status(actions, format, false);
+}
+
+fn change_status(actions: Vec<String>, format: OutputFormat, enable: bool) {
+    let mut document = ChangeDocument::default();
+
+    match for_each_pattern(
+        actions,
+        &mut document,
+        |available, list, pattern, document| {
+            change_pattern(available, list, pattern, document, enable)
         },
+    ) {
+        Ok(list) => {
+            if let Err(err) = dump_enabled_actions(&list) {
+                document.errors.push(err);
+            }
+        }
         Err(err) => {
-            printer.print_detailed_error(err);
+            document.errors.push(err);
         }
     };
+
+    format.print(&document, print_changes);
+    if !document.errors.is_empty() {
+        exit(1);
+    }
+}
+
+/// The groups and the actions that can be enabled.
+struct Available {
+    groups: Vec<String>,
+    actions: Vec<ActionPath>,
 }
 
-fn enable_pattern(
-    printer: &mut Printer,
-    available: &Vec<ActionPath>,
+fn change_pattern(
+    available: &Available,
     enabled: &mut EnabledList,
     pattern: ActionPathPattern,
+    document: &mut ChangeDocument,
+    enable: bool,
 ) {
+    if pattern.is_group_pattern() {
+        change_group_pattern(available, enabled, pattern, document, enable);
+        return;
+    }
+
     let mut matched_any = false;
-    for path in available {
+    for path in &available.actions {
         if path.matches(&pattern) {
             matched_any = true;
-            if enabled.enable_path(path.clone()) {
-                printer
-                    .print_text("●".green())
-                    .print_em_text(path)
-                    .println_text("is now enabled");
+            let changed = if enable {
+                enabled.enable_path(path.clone())
             } else {
-                printer
-                    .print_text("~".green())
-                    .print_em_text(path)
-                    .println_text("is already enabled");
-            }
+                enabled.disable_path(path.clone())
+            };
+            document.actions.push(ChangedAction {
+                path: path.clone(),
+                enabled: enable,
+                changed,
+            });
         }
     }
     if !matched_any {
-        printer
-            .print_text("pattern")
-            .print_em_text(pattern)
-            .println_text("did not match any actions");
+        document.unmatched_patterns.push(pattern.to_string());
     }
 }
 
-pub fn disable(actions: Vec<String>) {
-    let mut printer = Printer::new();
-
-    match for_each_pattern(actions, &mut printer, disable_pattern) {
-        Ok(list) => match dump_enabled_actions(&list) {
-            Ok(()) => {}
-            Err(err) => {
-                printer.print_detailed_error(err);
-            }
-        },
-        Err(err) => {
-            printer.print_detailed_error(err);
-        }
-    };
-}
-
-fn disable_pattern(
-    printer: &mut Printer,
-    available: &Vec<ActionPath>,
+fn change_group_pattern(
+    available: &Available,
     enabled: &mut EnabledList,
     pattern: ActionPathPattern,
+    document: &mut ChangeDocument,
+    enable: bool,
 ) {
     let mut matched_any = false;
-    for path in available {
-        if path.matches(&pattern) {
+    for group in &available.groups {
+        if pattern.matches_group(group) {
             matched_any = true;
-            if enabled.disable_path(path.clone()) {
-                printer
-                    .print_text("○")
-                    .print_em_text(path)
-                    .println_text("is now disabled");
+            let changed = if enable {
+                enabled.enable_group(group)
             } else {
-                printer
-                    .print_text("~")
-                    .print_em_text(path)
-                    .println_text("is already disabled");
-            }
+                enabled.disable_group(group)
+            };
+            document.groups.push(ChangedGroup {
+                group: group.clone(),
+                enabled: enable,
+                changed,
+            });
         }
     }
     if !matched_any {
+        document.unmatched_patterns.push(pattern.to_string());
+    }
+}
+
+fn print_changes(printer: &mut Printer, document: &ChangeDocument) {
+    for group in &document.groups {
+        match (group.enabled, group.changed) {
+            (true, true) => printer
+                .print_text("●".green())
+                .print_text("group")
+                .print_em_text(&group.group)
+                .println_text("is now enabled, with the actions added to it later"),
+            (true, false) => printer
+                .print_text("~".green())
+                .print_text("group")
+                .print_em_text(&group.group)
+                .println_text("is already enabled"),
+            (false, true) => printer
+                .print_text("○")
+                .print_text("group")
+                .print_em_text(&group.group)
+                .println_text("is now disabled"),
+            (false, false) => printer
+                .print_text("~")
+                .print_text("group")
+                .print_em_text(&group.group)
+                .println_text("is already disabled"),
+        };
+    }
+    for action in &document.actions {
+        match (action.enabled, action.changed) {
+            (true, true) => printer
+                .print_text("●".green())
+                .print_em_text(&action.path)
+                .println_text("is now enabled"),
+            (true, false) => printer
+                .print_text("~".green())
+                .print_em_text(&action.path)
+                .println_text("is already enabled"),
+            (false, true) => printer
+                .print_text("○")
+                .print_em_text(&action.path)
+                .println_text("is now disabled"),
+            (false, false) => printer
+                .print_text("~")
+                .print_em_text(&action.path)
+                .println_text("is already disabled"),
+        };
+    }
+    for pattern in &document.unmatched_patterns {
         printer
             .print_text("pattern")
             .print_em_text(pattern)
             .println_text("did not match any actions");
     }
+    for error in &document.errors {
+        printer.print_detailed_error(error);
+    }
 }
 
 fn for_each_pattern<
-    H: FnMut(&mut Printer, &Vec<ActionPath>, &mut EnabledList, ActionPathPattern),
+    H: FnMut(&Available, &mut EnabledList, ActionPathPattern, &mut ChangeDocument),
 >(
     actions: Vec<String>,
-    printer: &mut Printer,
+    document: &mut ChangeDocument,
     mut handler: H,
 ) -> Result<EnabledList> {
-    let available = try_read_all_groups()?
-        .into_iter()
-        .flat_map(group_to_action_path_list)
-        .collect();
+    let groups = try_read_all_groups()?;
+    let available = Available {
+        groups: groups.iter().map(|(name, _)| name.clone()).collect(),
+        actions: groups
+            .into_iter()
+            .flat_map(group_to_action_path_list)
+            .collect(),
+    };
     let mut enabled = load_enabled_actions()?;
 
     for action in actions {
         match ActionPathPattern::parse(&action) {
-            Ok(pattern) => handler(printer, &available, &mut enabled, pattern),
+            Ok(pattern) => handler(&available, &mut enabled, pattern, document),
             Err(_) => {
-                printer.print_error(format!("'{}' is not a valid action path pattern", action));
+                document
+                    .errors
+                    .push(problem!("'{}' is not a valid action path pattern", action));
             }
         }
     }
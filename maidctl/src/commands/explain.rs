@@ -0,0 +1,335 @@
+use crate::printer::Printer;
+use maidono_core::actions::{
+    parameter_variable, Action, ActionIndex, ExecutionPlan, HostRef, Method, ResponseMode,
+    TriggerParameters,
+};
+use maidono_core::problem;
+use maidono_core::security::{
+    check_host_headers, delivery_id, extract_signature, signature_header,
+};
+use maidono_core::utils::{ActionPath, ErrorPrinter, Result};
+use owo_colors::OwoColorize;
+use std::fs;
+use std::path::PathBuf;
+
+pub struct ExplainOptions {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<String>,
+    pub payload_file: Option<PathBuf>,
+}
+
+pub fn explain(options: ExplainOptions) {
+    let mut printer = Printer::new();
+    if let Err(err) = explain_request(&mut printer, options) {
+        printer.print_detailed_error(&err);
+    }
+}
+
+fn explain_request(printer: &mut Printer, options: ExplainOptions) -> Result<()> {
+    let method: Method = options.method.parse()?;
+    let segments = path_segments(&options.url);
+    let headers = options
+        .headers
+        .iter()
+        .map(|header| parse_header(header))
+        .collect::<Result<Vec<(String, String)>>>()?;
+    let body = match &options.payload_file {
+        Some(path) => fs::read(path).map_err(|err| {
+            problem!("Unable to read the payload file {}", path.display()).because(err)
+        })?,
+        None => Vec::new(),
+    };
+
+    // the actions are loaded the same way as the server does, failing if it would fail
+    let actions = ActionIndex::load()
+        .map_err(|err| problem!("The server would refuse to load the actions").because(err))?;
+
+    printer
+        .print_text("Request")
+        .print_em_text(format!("{} /{}", method, segments.join("/")))
+        .println_text(format!("({} bytes of payload)", body.len()).dimmed())
+        .println();
+
+    // like the server, only the routes of the enabled actions are looked up
+    let matched = actions.lookup(method, segments.iter().map(String::as_str));
+    if matched.is_empty() {
+        explain_unmatched(printer, &actions, method, &segments);
+        return Ok(());
+    }
+
+    let mut accepted = Vec::new();
+    for (path, action, parameters) in matched {
+        if explain_action(
+            printer,
+            &actions,
+            path,
+            action,
+            &parameters,
+            &headers,
+            &body,
+        ) {
+            accepted.push((path, action));
+        }
+        printer.println();
+    }
+
+    if accepted.is_empty() {
+        printer.println_text("The server would respond with 400 Bad Request".red());
+    } else {
+        printer
+            .print_text("The server would respond with 200 OK and run")
+            .println_list(accepted.iter().map(|(path, _)| path.bold()));
+
+        // the actions waiting for their run respond with their own status when it fails
+        for (path, action) in &accepted {
+            if let ResponseMode::Wait(options) = action.response_mode() {
+                printer.println_text(
+                    format!(
+                        "If the run of {} fails, the server would respond with {} instead",
+                        path,
+                        options.error_status()
+                    )
+                    .dimmed(),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Explains why a request doesn't trigger anything.
+fn explain_unmatched(
+    printer: &mut Printer,
+    actions: &ActionIndex,
+    method: Method,
+    segments: &[String],
+) {
+    let disabled = actions.lookup_disabled(method, segments.iter().map(String::as_str));
+
+    print_step(printer, false, "route:");
+    if disabled.is_empty() {
+        printer.println_text("no action is triggered by this request");
+    } else {
+        printer
+            .print_text("only disabled actions are triggered by this request:")
+            .println_list(disabled.iter().map(|path| path.bold()));
+    }
+    printer.println();
+
+    if method == Method::Get {
+        printer.println_text("The server would serve the web app instead".red());
+    } else {
+        printer.println_text("The server would respond with 404 Not Found".red());
+    }
+    if let Some(path) = disabled.first() {
+        printer
+            .println_text(format!("To enable the action, run `maidctl enable {}`", path).dimmed());
+    }
+}
+
+/// Goes through the checks of the server for one of the matching actions, and tells whether the
+/// request would be accepted.
+fn explain_action(
+    printer: &mut Printer,
+    actions: &ActionIndex,
+    path: &ActionPath,
+    action: &Action,
+    parameters: &TriggerParameters,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> bool {
+    let host_ref = action.origin();
+    printer
+        .print_em_text(path)
+        .println_text(action.trigger().dimmed())
+        .indent();
+
+    print_step(printer, true, "route:");
+    if parameters.is_empty() {
+        printer.println_text("matches");
+    } else {
+        printer.print_text("matches with").println_list(
+            parameters
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        );
+    }
+    print_step(printer, true, "enabled");
+    printer.println();
+
+    let accepted = match check_host_headers(host_ref, headers) {
+        Ok(()) => {
+            print_step(printer, true, "origin:");
+            match host_ref {
+                HostRef::Any => printer.println_text("requests from any host are accepted"),
+                HostRef::GitHub => printer.println_text("the headers are the ones sent by GitHub"),
+                HostRef::Custom(_) => printer.println_text(format!(
+                    "the headers of requests from {} are not checked",
+                    host_ref
+                )),
+            };
+            true
+        }
+        Err(err) => {
+            print_step(printer, false, "origin:");
+            printer
+                .println_text(format!("the request does not come from {}", host_ref))
+                .indent()
+                .print_detailed_error(&err)
+                .unindent();
+            false
+        }
+    };
+
+    let accepted = accepted && check_signature(printer, action, headers, body);
+
+    if !accepted {
+        printer
+            .println_text("The request would be rejected for this action".red())
+            .unindent();
+        return false;
+    }
+
+    if let Some(delivery_id) = delivery_id(host_ref, headers) {
+        printer.print_text("delivery:").println_text(delivery_id);
+    }
+    match action.response_mode() {
+        ResponseMode::Immediate => {
+            printer.print_text("response:").println_text("right away");
+        }
+        ResponseMode::Wait(options) => {
+            printer.print_text("response:").println_text(format!(
+                "once the run is finished, or after {} seconds (with status {} if it fails)",
+                options.timeout(),
+                options.error_status()
+            ));
+        }
+    }
+    if !parameters.is_empty() {
+        printer.print_text("environment:").println_list(
+            parameters
+                .iter()
+                .map(|(name, value)| format!("{}={}", parameter_variable(name), value)),
+        );
+    }
+
+    printer.println_text("runs:").indent();
+    let steps = actions
+        .plan(path)
+        .map(ExecutionPlan::steps)
+        .unwrap_or_default();
+    for step in steps {
+        printer.println_text(step.bold()).indent();
+        if let Some(commands) = actions.get(step).map(|(_, action)| action.action()) {
+            for command in commands {
+                printer.print_text("$".dimmed()).println_text(command);
+            }
+        }
+        printer.unindent();
+    }
+    printer.unindent().unindent();
+    true
+}
+
+fn check_signature(
+    printer: &mut Printer,
+    action: &Action,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> bool {
+    let host_ref = action.origin();
+    let Some(secret) = action.secret() else {
+        print_step(printer, true, "signature:");
+        printer.println_text("the action has no secret, the request is not checked");
+        return true;
+    };
+    let Some(header) = signature_header(host_ref) else {
+        print_step(printer, false, "signature:");
+        printer.println_text(format!(
+            "the action has a secret, but requests from {} are never signed",
+            host_ref
+        ));
+        return false;
+    };
+
+    match extract_signature(host_ref, headers) {
+        Some(signature) if signature.matches(secret, body) => {
+            print_step(printer, true, "signature:");
+            printer.println_text(format!("the {} header matches the secret", header));
+            true
+        }
+        Some(_) => {
+            print_step(printer, false, "signature:");
+            printer.println_text(format!(
+                "the {} header does not match the secret and the payload",
+                header
+            ));
+            false
+        }
+        None => {
+            print_step(printer, false, "signature:");
+            printer.println_text(format!("the {} header is missing or invalid", header));
+            false
+        }
+    }
+}
+
+fn print_step(printer: &mut Printer, passed: bool, step: &str) {
+    if passed {
+        printer.print_text("✓".green());
+    } else {
+        printer.print_text("✗".red());
+    }
+    printer.print_text(step);
+}
+
+/// The decoded segments of the path of a URL, which may be given without its scheme and host.
+fn path_segments(url: &str) -> Vec<String> {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |start| &rest[start..]),
+        None => url,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(percent_decode)
+        .collect()
+}
+
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_header(header: &str) -> Result<(String, String)> {
+    match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_owned(), value.trim().to_owned()))
+        }
+        _ => Err(problem!(
+            "Invalid header '{}', expected something like 'User-Agent: curl'",
+            header
+        )),
+    }
+}
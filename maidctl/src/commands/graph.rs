@@ -0,0 +1,245 @@
+use crate::printer::Printer;
+use maidono_core::actions::{try_read_all_groups, Dependencies, Group, Reference, ReferenceKind};
+use maidono_core::enabled_actions::{load_enabled_actions, EnabledList};
+use maidono_core::problem;
+use maidono_core::utils::{ActionPath, ActionPathPattern, ErrorPrinter, Result};
+use owo_colors::OwoColorize;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub fn graph(pattern: Option<String>, dot: bool) {
+    let mut printer = Printer::new();
+    if let Err(err) = print_graph(&mut printer, pattern, dot) {
+        printer.print_detailed_error(&err);
+    }
+}
+
+fn print_graph(printer: &mut Printer, pattern: Option<String>, dot: bool) -> Result<()> {
+    let pattern = pattern
+        .as_deref()
+        .map(ActionPathPattern::parse)
+        .transpose()?;
+
+    let enabled = match load_enabled_actions() {
+        Ok(el) => Some(el),
+        Err(err) => {
+            printer.print_detailed_error(&err);
+            None
+        }
+    };
+
+    let mut groups: Vec<(String, Group)> = Vec::new();
+    for (name, result) in try_read_all_groups()? {
+        match result {
+            Ok(group) => groups.push((name, group)),
+            Err(err) => {
+                // the graph is still useful, the actions of this group are shown as missing
+                printer.print_detailed_warning(&problem!("Invalid group '{}'", name).because(err));
+            }
+        }
+    }
+    let dependencies =
+        Dependencies::from_groups(groups.iter().map(|(name, group)| (name.as_str(), group)));
+
+    let roots: Vec<ActionPath> = dependencies
+        .paths()
+        .filter(|path| pattern.as_ref().is_none_or(|pattern| path.matches(pattern)))
+        .cloned()
+        .collect();
+    if roots.is_empty() {
+        return Err(match pattern {
+            Some(pattern) => problem!("Pattern '{}' did not match any actions", pattern),
+            None => problem!("There are no actions"),
+        });
+    }
+
+    let graph = Graph {
+        dependencies,
+        enabled,
+    };
+    if dot {
+        graph.print_dot(&roots);
+    } else {
+        for root in &roots {
+            graph.print_tree(printer, root);
+        }
+    }
+    Ok(())
+}
+
+struct Graph<'a> {
+    dependencies: Dependencies<'a>,
+    enabled: Option<EnabledList>,
+}
+
+impl Graph<'_> {
+    fn is_enabled(&self, path: &ActionPath) -> Option<bool> {
+        self.enabled
+            .as_ref()
+            .map(|enabled| enabled.is_path_enabled(path))
+    }
+
+    fn print_tree(&self, printer: &mut Printer, root: &ActionPath) {
+        match self.is_enabled(root) {
+            Some(true) => printer.print_text("●".green()),
+            Some(false) => printer.print_text("○"),
+            None => printer,
+        }
+        .print_em_text(root);
+        if let Some(action) = self.dependencies.get(root) {
+            printer.print_text(action.trigger().dimmed());
+        }
+        if self.is_enabled(root) == Some(false) {
+            printer.print_text("(disabled)");
+        }
+        printer.println();
+
+        let mut ancestors = vec![root.clone()];
+        self.print_references(printer, root, "", &mut ancestors);
+
+        match self.dependencies.plan(root) {
+            Ok(plan) if plan.steps().len() > 1 => {
+                printer
+                    .indent()
+                    .print_text("runs:")
+                    .println_text(plan.to_string())
+                    .unindent();
+            }
+            Ok(_) => {}
+            Err(_) => {
+                // the missing actions and cycles that prevent planning are marked in the tree
+                printer
+                    .indent()
+                    .print_text("runs:")
+                    .println_text("nothing, the actions cannot be ordered".red())
+                    .unindent();
+            }
+        }
+    }
+
+    fn print_references(
+        &self,
+        printer: &mut Printer,
+        path: &ActionPath,
+        prefix: &str,
+        ancestors: &mut Vec<ActionPath>,
+    ) {
+        let references = self.dependencies.references_of(path);
+        let count = references.len();
+        for (index, reference) in references.into_iter().enumerate() {
+            let last = index + 1 == count;
+            let branch = if last { "└─" } else { "├─" };
+            let target = ActionPath::parse(&reference.to).ok();
+
+            let mut line = format!("{}{} {} ", prefix, branch, kind_name(reference.kind));
+            let repeated = target
+                .as_ref()
+                .is_some_and(|target| ancestors.contains(target));
+            // an action may be referenced back without any cycle, like with `before` on one side
+            // and `after` on the other, it's only a cycle when the actions can't be ordered
+            let cycle = repeated && self.dependencies.plan(&ancestors[0]).is_err();
+            if !reference.exists {
+                line.push_str(&format!("{} {} (missing)", "✗".red(), reference.to));
+            } else {
+                match target.as_ref().and_then(|target| self.is_enabled(target)) {
+                    Some(true) => line.push_str(&format!("{} {}", "●".green(), reference.to)),
+                    Some(false) => line.push_str(&format!("○ {} (disabled)", reference.to)),
+                    None => line.push_str(&reference.to),
+                }
+                if cycle {
+                    line.push_str(&format!(" {}", "(cycle)".red()));
+                } else if repeated {
+                    line.push_str(&format!(" {}", "(see above)".dimmed()));
+                }
+            }
+            printer.indent().println_text(line).unindent();
+
+            if let Some(target) = target.filter(|_| reference.exists && !repeated) {
+                let prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+                ancestors.push(target.clone());
+                self.print_references(printer, &target, &prefix, ancestors);
+                ancestors.pop();
+            }
+        }
+    }
+
+    /// Prints the actions reachable from the roots as a Graphviz graph. The edges go from an
+    /// action to the action that runs after it.
+    fn print_dot(&self, roots: &[ActionPath]) {
+        let mut nodes: BTreeMap<String, bool> = BTreeMap::new();
+        let mut edges: BTreeSet<(String, String, &'static str)> = BTreeSet::new();
+        let mut pending: Vec<ActionPath> = roots.to_vec();
+
+        while let Some(path) = pending.pop() {
+            if nodes.insert(path.to_string(), true).is_some() {
+                continue;
+            }
+            for Reference {
+                from,
+                kind,
+                to,
+                exists,
+            } in self.dependencies.references_of(&path)
+            {
+                let from = from.to_string();
+                match kind {
+                    ReferenceKind::Before => edges.insert((to.clone(), from, "before")),
+                    ReferenceKind::After => edges.insert((from, to.clone(), "after")),
+                };
+                if exists {
+                    if let Ok(target) = ActionPath::parse(&to) {
+                        pending.push(target);
+                    }
+                } else {
+                    nodes.entry(to).or_insert(false);
+                }
+            }
+        }
+
+        println!("digraph maidono {{");
+        println!("  rankdir=LR;");
+        println!("  node [shape=box];");
+        for (name, exists) in &nodes {
+            let path = ActionPath::parse(name).ok();
+            let action = path.as_ref().and_then(|path| self.dependencies.get(path));
+            let attributes = match (exists, action) {
+                (true, Some(action)) => {
+                    let style = match path.as_ref().and_then(|path| self.is_enabled(path)) {
+                        Some(false) => ", style=dashed, color=gray40, fontcolor=gray40",
+                        _ => "",
+                    };
+                    format!(
+                        "label=\"{}\\n{}\"{}",
+                        dot_escape(name),
+                        dot_escape(&action.trigger().to_string()),
+                        style
+                    )
+                }
+                _ => format!(
+                    "label=\"{}\\n(missing)\", style=dotted, color=red, fontcolor=red",
+                    dot_escape(name)
+                ),
+            };
+            println!("  \"{}\" [{}];", dot_escape(name), attributes);
+        }
+        for (from, to, label) in &edges {
+            println!(
+                "  \"{}\" -> \"{}\" [label=\"{}\"];",
+                dot_escape(from),
+                dot_escape(to),
+                label
+            );
+        }
+        println!("}}");
+    }
+}
+
+fn kind_name(kind: ReferenceKind) -> &'static str {
+    match kind {
+        ReferenceKind::Before => "before:",
+        ReferenceKind::After => "after:",
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
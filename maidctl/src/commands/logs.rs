@@ -0,0 +1,169 @@
+use crate::printer::Printer;
+use maidono_core::control::{
+    ControlRequest, ControlResponse, LogEntry, OutputStream, RunEvent, RunSummary,
+};
+use maidono_core::problem;
+use maidono_core::utils::path::CONTROL_SOCKET;
+use maidono_core::utils::{ActionPath, ActionPathPattern, ErrorPrinter, Result};
+use owo_colors::OwoColorize;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// How often the server is asked for new output when following a run.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct LogsOptions {
+    pub pattern: Option<String>,
+    pub run: Option<String>,
+    pub follow: bool,
+    pub failed: bool,
+}
+
+pub fn logs(options: LogsOptions) {
+    let mut printer = Printer::new();
+    let socket = Path::new(CONTROL_SOCKET);
+
+    let result = find_run(socket, &options)
+        .and_then(|id| print_log(&mut printer, socket, &id, options.follow));
+    if let Err(err) = result {
+        printer.print_detailed_error(&err);
+    }
+}
+
+/// Finds the ID of the run to show: the one given, or the last one matching the options.
+fn find_run(socket: &Path, options: &LogsOptions) -> Result<String> {
+    if let Some(id) = &options.run {
+        return Ok(id.clone());
+    }
+
+    let pattern = options
+        .pattern
+        .as_deref()
+        .map(ActionPathPattern::parse)
+        .transpose()?;
+    let runs = match ControlRequest::History.send(socket)?.into_result()? {
+        ControlResponse::Runs { runs } => runs,
+        _ => return Err(problem!("Unexpected response from the server")),
+    };
+
+    runs.into_iter()
+        .find(|run| {
+            let action_matches = pattern.as_ref().is_none_or(|pattern| {
+                ActionPath::parse(&run.action).is_ok_and(|path| path.matches(pattern))
+            });
+            action_matches && (!options.failed || run.has_failed())
+        })
+        .map(|run| run.id)
+        .ok_or_else(|| match (&options.pattern, options.failed) {
+            (Some(pattern), true) => problem!("No failed run of '{}' was found", pattern),
+            (Some(pattern), false) => problem!("No run of '{}' was found", pattern),
+            (None, true) => problem!("No failed run was found"),
+            (None, false) => problem!("No run was found"),
+        })
+}
+
+fn print_log(printer: &mut Printer, socket: &Path, id: &str, follow: bool) -> Result<()> {
+    let mut from = 0;
+    loop {
+        let request = ControlRequest::RunLog {
+            run: id.to_owned(),
+            from,
+        };
+        let (run, entries) = match request.send(socket)?.into_result()? {
+            ControlResponse::RunLog { run, entries } => (run, entries),
+            _ => return Err(problem!("Unexpected response from the server")),
+        };
+
+        if from == 0 {
+            print_header(printer, &run);
+        }
+        from += entries.len();
+        for entry in entries {
+            print_entry(printer, entry);
+        }
+
+        if run.is_finished() {
+            return Ok(());
+        }
+        if !follow {
+            printer.println_text(
+                "(the run is still in progress, use --follow to print the rest of its output)"
+                    .dimmed(),
+            );
+            return Ok(());
+        }
+        sleep(FOLLOW_INTERVAL);
+    }
+}
+
+fn print_header(printer: &mut Printer, run: &RunSummary) {
+    printer
+        .print_text("Run")
+        .print_em_text(&run.id)
+        .print_text("of")
+        .print_em_text(&run.action)
+        .println_text(format!("started at {}", run.started_at));
+    if run.chain.len() > 1 {
+        printer
+            .indent()
+            .print_text("plan:")
+            .println_list(&run.chain)
+            .unindent();
+    }
+}
+
+fn print_entry(printer: &mut Printer, entry: LogEntry) {
+    // only the time of the day is shown, the date is in the header
+    let time = entry.time.get(11..23).unwrap_or(&entry.time);
+    printer.print_text(time.dimmed());
+
+    match entry.event {
+        RunEvent::ActionStarted { action } => {
+            printer
+                .print_text("▸".blue())
+                .print_em_text(action)
+                .println();
+        }
+        RunEvent::ActionSkipped { action } => {
+            printer
+                .print_text("○")
+                .print_em_text(action)
+                .println_text("skipped");
+        }
+        RunEvent::CommandStarted { command } => {
+            printer.print_text("$").println_text(command);
+        }
+        RunEvent::Output {
+            stream: OutputStream::Stdout,
+            line,
+        } => {
+            printer.println_text(format!("  {}", line));
+        }
+        RunEvent::Output {
+            stream: OutputStream::Stderr,
+            line,
+        } => {
+            printer.println_text(format!("  {}", line).yellow());
+        }
+        RunEvent::CommandExited { exit_code: Some(0) } => {
+            printer.println_text("✓ exit code 0".green());
+        }
+        RunEvent::CommandExited {
+            exit_code: Some(code),
+        } => {
+            printer.println_text(format!("✗ exit code {}", code).red());
+        }
+        RunEvent::CommandExited { exit_code: None } => {
+            printer.println_text("✗ no exit code".red());
+        }
+        RunEvent::OutputTruncated => {
+            printer.println_text("… the rest of the output was not kept".dimmed());
+        }
+        RunEvent::Finished { status } => {
+            printer
+                .print_text("●")
+                .println_text(format!("run {}", status));
+        }
+    }
+}
@@ -1,11 +1,28 @@
+mod check;
 mod display_config;
+mod edit;
 mod edit_config;
+mod explain;
+mod graph;
+mod logs;
+mod new;
+mod prompt;
 mod reload;
-mod systemctl;
+mod runs;
+mod service;
+mod status;
 mod test;
 
-pub use display_config::{list, show};
+pub use check::check;
+pub use display_config::{list, show, ListFilter};
+pub use edit::edit;
 pub use edit_config::{disable, enable};
+pub use explain::{explain, ExplainOptions};
+pub use graph::graph;
+pub use logs::{logs, LogsOptions};
+pub use new::{new, NewActionOptions};
 pub use reload::reload;
-pub use systemctl::systemctl;
+pub use runs::{runs, show_run, RunsFilter};
+pub use service::service;
+pub use status::status;
 pub use test::{test, FileTestPayload, NoTestPayload, StringTestPayload};
@@ -0,0 +1,132 @@
+use crate::commands::prompt::{ask, ask_yes_no};
+use crate::commands::{enable, reload};
+use crate::output::OutputFormat;
+use crate::printer::Printer;
+use maidono_core::actions::{
+    append_action_entry, find_group_file, new_group_file, replace_group_file, ActionEntry,
+    Commands, HostRef, Trigger,
+};
+use maidono_core::problem;
+use maidono_core::utils::{ActionPath, ErrorPrinter, Result};
+use owo_colors::OwoColorize;
+use std::fs::{read_to_string, File};
+use std::io::Read;
+
+/// The size of the generated secrets, in bytes.
+const SECRET_SIZE: usize = 32;
+
+pub struct NewActionOptions {
+    pub path: Option<String>,
+    pub on: Option<String>,
+    pub from: Option<String>,
+    pub run: Vec<String>,
+    pub secret: bool,
+    pub enable: bool,
+}
+
+pub fn new(mut options: NewActionOptions) {
+    let mut printer = Printer::new();
+
+    // anything that is missing is asked for
+    let interactive = options.path.is_none() || options.on.is_none() || options.run.is_empty();
+    if interactive {
+        if let Err(err) = ask_for_missing_options(&mut options) {
+            printer.print_detailed_error(&err);
+            return;
+        }
+    }
+
+    match create_action(&options) {
+        Ok((path, secret)) => {
+            printer
+                .print_text("+".green())
+                .print_em_text(&path)
+                .println_text("was created");
+            if let Some(secret) = secret {
+                printer.indent().print_text("secret:").println_text(secret);
+                printer.println_text("Use this secret when setting up the webhook.");
+                printer.unindent();
+            }
+            if options.enable {
+                enable(vec![path.to_string()], OutputFormat::Text);
+            }
+            // the server only routes the new action once it reloads the actions
+            reload();
+        }
+        Err(err) => {
+            printer.print_detailed_error(&err);
+        }
+    }
+}
+
+fn create_action(options: &NewActionOptions) -> Result<(ActionPath, Option<String>)> {
+    let path = ActionPath::parse(options.path.as_deref().unwrap_or_default())?;
+    let trigger = Trigger::parse(options.on.as_deref().unwrap_or_default())?;
+    let (group, name) = path.clone().into_parts();
+
+    let mut entry = ActionEntry::new(name, trigger, Commands::new(options.run.clone()));
+    if let Some(origin) = &options.from {
+        entry = entry.with_origin(HostRef::parse(origin));
+    }
+    let secret = if options.secret {
+        let secret = generate_secret()?;
+        entry = entry.with_secret(secret.clone());
+        Some(secret)
+    } else {
+        None
+    };
+
+    let (file, source) = match find_group_file(&group)? {
+        Some(file) => {
+            let source = read_to_string(&file)?;
+            (file, source)
+        }
+        None => (new_group_file(&group)?, String::new()),
+    };
+    let appended = append_action_entry(&source, &entry)
+        .map_err(|err| problem!("Unable to add the action to {}", file.display()).because(err))?;
+    replace_group_file(&file, &appended)?;
+
+    Ok((path, secret))
+}
+
+fn generate_secret() -> Result<String> {
+    let mut bytes = [0u8; SECRET_SIZE];
+    File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut bytes))
+        .map_err(|err| problem!("Unable to generate a secret").because(err))?;
+
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn ask_for_missing_options(options: &mut NewActionOptions) -> Result<()> {
+    if options.path.is_none() {
+        options.path = Some(ask("Action (group/name)")?);
+    }
+    if options.on.is_none() {
+        options.on = Some(ask("Trigger (e.g. POST /deploy)")?);
+    }
+    if options.from.is_none() {
+        let from = ask("Origin (github, * or empty for any)")?;
+        if !from.is_empty() {
+            options.from = Some(from);
+        }
+    }
+    if options.run.is_empty() {
+        println!(" Commands, one per line (end with an empty line):");
+        loop {
+            let command = ask(">")?;
+            if command.is_empty() {
+                break;
+            }
+            options.run.push(command);
+        }
+    }
+    if !options.secret {
+        options.secret = ask_yes_no("Generate a secret?")?;
+    }
+    if !options.enable {
+        options.enable = ask_yes_no("Enable the action?")?;
+    }
+    Ok(())
+}
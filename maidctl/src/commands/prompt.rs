@@ -0,0 +1,21 @@
+use maidono_core::problem;
+use maidono_core::utils::Result;
+use owo_colors::OwoColorize;
+use std::io::{stdin, stdout, Write};
+
+/// Asks a question on the terminal, and returns the answer without the surrounding whitespace.
+pub fn ask(question: &str) -> Result<String> {
+    print!(" {} ", question.bold());
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer)? == 0 {
+        return Err(problem!("No answer was given"));
+    }
+    Ok(answer.trim().to_owned())
+}
+
+pub fn ask_yes_no(question: &str) -> Result<bool> {
+    let answer = ask(&format!("{} [y/N]", question))?;
+    Ok(matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"))
+}
@@ -1 +1,41 @@
-pub fn reload() {}
+use crate::printer::Printer;
+use maidono_core::control::{ControlRequest, ControlResponse};
+use maidono_core::utils::path::CONTROL_SOCKET;
+use maidono_core::utils::ErrorPrinter;
+use owo_colors::OwoColorize;
+use std::path::Path;
+use std::process::exit;
+
+pub fn reload() {
+    let mut printer = Printer::new();
+
+    match ControlRequest::Reload.send(Path::new(CONTROL_SOCKET)) {
+        Ok(ControlResponse::Reloaded { enabled_actions }) => {
+            printer
+                .print_text("●".green())
+                .print_text("Reloaded the actions,")
+                .print_em_text(enabled_actions)
+                .println_text("enabled");
+        }
+        Ok(ControlResponse::Error { message }) => {
+            printer
+                .print_error("The server was unable to reload the actions :")
+                .indent()
+                .print_error(message)
+                .unindent();
+            exit(1);
+        }
+        Ok(_) => {
+            printer.print_error("Unexpected response from the server");
+            exit(1);
+        }
+        Err(err) => {
+            printer.print_warning("Unable to reach the server");
+            printer.indent().print_detailed_error(&err).unindent();
+            printer.println_text(
+                "If the server is not running, the actions will be loaded when it starts.",
+            );
+            exit(1);
+        }
+    }
+}
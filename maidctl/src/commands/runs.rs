@@ -0,0 +1,296 @@
+use crate::output::OutputFormat;
+use crate::printer::Printer;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use maidono_core::control::{ActionOutcome, ControlRequest, ControlResponse, RunSummary};
+use maidono_core::problem;
+use maidono_core::utils::path::CONTROL_SOCKET;
+use maidono_core::utils::{ActionPath, ActionPathPattern, Error, ErrorPrinter, Result};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::path::Path;
+use std::process::exit;
+
+/// Restricts the runs listed by `maidctl runs`.
+pub struct RunsFilter {
+    pub pattern: Option<String>,
+    pub status: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct RunsDocument {
+    runs: Vec<RunSummary>,
+    errors: Vec<Error>,
+}
+
+#[derive(Serialize)]
+struct RunDocument {
+    #[serde(flatten)]
+    run: Option<RunSummary>,
+    actions: Vec<ActionOutcome>,
+    errors: Vec<Error>,
+}
+
+pub fn runs(filter: RunsFilter, format: OutputFormat) {
+    let document = match find_runs(&filter) {
+        Ok(runs) => RunsDocument {
+            runs,
+            errors: Vec::new(),
+        },
+        Err(err) => RunsDocument {
+            runs: Vec::new(),
+            errors: vec![err],
+        },
+    };
+    format.print(&document, print_runs);
+    if !document.errors.is_empty() {
+        exit(1);
+    }
+}
+
+fn find_runs(filter: &RunsFilter) -> Result<Vec<RunSummary>> {
+    let pattern = filter
+        .pattern
+        .as_deref()
+        .map(ActionPathPattern::parse)
+        .transpose()?;
+    let since = filter.since.as_deref().map(parse_time).transpose()?;
+    let until = filter.until.as_deref().map(parse_time).transpose()?;
+
+    let runs = match ControlRequest::History
+        .send(Path::new(CONTROL_SOCKET))?
+        .into_result()?
+    {
+        ControlResponse::Runs { runs } => runs,
+        _ => return Err(problem!("Unexpected response from the server")),
+    };
+
+    Ok(runs
+        .into_iter()
+        .filter(|run| {
+            pattern.as_ref().is_none_or(|pattern| {
+                ActionPath::parse(&run.action).is_ok_and(|path| path.matches(pattern))
+            })
+        })
+        .filter(|run| {
+            filter
+                .status
+                .as_ref()
+                .is_none_or(|status| &run.status == status)
+        })
+        .filter(|run| {
+            let started_at = DateTime::parse_from_rfc3339(&run.started_at).ok();
+            since.is_none_or(|since| started_at.is_some_and(|started_at| started_at >= since))
+                && until.is_none_or(|until| started_at.is_some_and(|started_at| started_at < until))
+        })
+        .take(filter.limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// Reads a time given as a duration before now (like `30m`, `2h` or `7d`), a local date or date
+/// and time, or an RFC 3339 timestamp.
+fn parse_time(spec: &str) -> Result<DateTime<Utc>> {
+    let invalid = || {
+        problem!(
+            "Invalid time '{}', expected a duration like 30m, 2h or 7d, or a date like 2024-11-20 or '2024-11-20 18:00'",
+            spec
+        )
+    };
+
+    let relative = ["s", "m", "h", "d"].into_iter().find_map(|unit| {
+        let amount: i64 = spec.strip_suffix(unit)?.parse().ok()?;
+        Some((unit, amount))
+    });
+    if let Some((unit, amount)) = relative {
+        if amount <= 0 {
+            return Err(problem!(
+                "Invalid time '{}', the duration must be positive",
+                spec
+            ));
+        }
+        let delta = match unit {
+            "s" => TimeDelta::try_seconds(amount),
+            "m" => TimeDelta::try_minutes(amount),
+            "h" => TimeDelta::try_hours(amount),
+            _ => TimeDelta::try_days(amount),
+        };
+        return delta
+            .and_then(|delta| Utc::now().checked_sub_signed(delta))
+            .ok_or_else(|| problem!("Invalid time '{}', the duration is too long", spec));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(spec) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(spec, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(spec, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(spec, "%Y-%m-%d")
+                .map(|date| date.and_time(Default::default()))
+        })
+        .map_err(|_| invalid())?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(invalid)
+}
+
+fn print_runs(printer: &mut Printer, document: &RunsDocument) {
+    if document.runs.is_empty() && document.errors.is_empty() {
+        printer.println_text("No runs found");
+    }
+
+    for run in &document.runs {
+        print_status(printer, &run.status);
+        printer
+            .print_em_text(&run.id)
+            .print_em_text(&run.action)
+            .print_text(local_time(&run.started_at));
+        if let Some(duration) = run.duration_ms {
+            printer.print_text(format!("in {}", format_duration(duration)));
+        }
+        printer.println_text(format!("[{}]", run.status));
+
+        printer.indent();
+        if run.chain.len() > 1 {
+            printer.print_text("plan:").println_list(&run.chain);
+        }
+        if let Some(delivery) = &run.delivery {
+            printer.print_text("delivery:").println_text(delivery);
+        }
+        printer.unindent();
+    }
+
+    for error in &document.errors {
+        printer.print_detailed_error(error);
+    }
+}
+
+pub fn show_run(id: String, format: OutputFormat) {
+    let request = ControlRequest::Run { run: id };
+    let result = request
+        .send(Path::new(CONTROL_SOCKET))
+        .and_then(ControlResponse::into_result);
+    let document = match result {
+        Ok(ControlResponse::Run { run, actions }) => RunDocument {
+            run: Some(run),
+            actions,
+            errors: Vec::new(),
+        },
+        Ok(_) => RunDocument {
+            run: None,
+            actions: Vec::new(),
+            errors: vec![problem!("Unexpected response from the server")],
+        },
+        Err(err) => RunDocument {
+            run: None,
+            actions: Vec::new(),
+            errors: vec![err],
+        },
+    };
+    format.print(&document, print_run);
+    if !document.errors.is_empty() {
+        exit(1);
+    }
+}
+
+fn print_run(printer: &mut Printer, document: &RunDocument) {
+    for error in &document.errors {
+        printer.print_detailed_error(error);
+    }
+    let Some(run) = &document.run else {
+        return;
+    };
+
+    print_status(printer, &run.status);
+    printer
+        .print_text("Run")
+        .print_em_text(&run.id)
+        .print_text("of")
+        .print_em_text(&run.action)
+        .println_text(format!("[{}]", run.status))
+        .indent();
+
+    printer
+        .print_text("started at:")
+        .println_text(local_time(&run.started_at));
+    if let Some(finished_at) = &run.finished_at {
+        printer
+            .print_text("finished at:")
+            .println_text(local_time(finished_at));
+    }
+    if let Some(duration) = run.duration_ms {
+        printer
+            .print_text("duration:")
+            .println_text(format_duration(duration));
+    }
+    if let Some(delivery) = &run.delivery {
+        printer.print_text("delivery:").println_text(delivery);
+    }
+    printer.print_text("plan:").println_list(&run.chain);
+
+    for action in &document.actions {
+        print_status(printer, &action.status);
+        printer
+            .print_em_text(&action.action)
+            .println_text(format!("[{}]", action.status))
+            .indent();
+        for command in &action.commands {
+            let exit = match command.exit_code {
+                Some(code) => format!("exit code {}", code),
+                None => String::from("no exit code"),
+            };
+            printer
+                .print_text("$")
+                .print_text(&command.command)
+                .println_text(exit.dimmed());
+        }
+        printer.unindent();
+    }
+
+    printer
+        .println()
+        .println_text(
+            format!(
+                "The output of the run is shown by `maidctl logs --run {}`",
+                run.id
+            )
+            .dimmed(),
+        )
+        .unindent();
+}
+
+fn print_status(printer: &mut Printer, status: &str) {
+    match status {
+        "succeeded" => printer.print_text("●".green()),
+        "failed" | "timeout" => printer.print_text("✗".red()),
+        "running" => printer.print_text("▸".blue()),
+        _ => printer.print_text("○"),
+    };
+}
+
+/// Shows a timestamp of the server in the local time zone.
+fn local_time(timestamp: &str) -> String {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(time) => time
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        Err(_) => timestamp.to_owned(),
+    }
+}
+
+fn format_duration(milliseconds: u64) -> String {
+    match milliseconds {
+        0..1000 => format!("{}ms", milliseconds),
+        1000..60_000 => format!("{:.1}s", milliseconds as f64 / 1000.0),
+        _ => format!(
+            "{}m{}s",
+            milliseconds / 60_000,
+            milliseconds % 60_000 / 1000
+        ),
+    }
+}
@@ -0,0 +1,12 @@
+use crate::printer::Printer;
+use crate::service::{ServiceAction, ServiceManager};
+
+pub fn service(action: ServiceAction) {
+    let mut printer = Printer::new();
+
+    let result =
+        ServiceManager::from_config().and_then(|manager| manager.run(&mut printer, action));
+    if let Err(err) = result {
+        printer.print_detailed_error(&err);
+    }
+}
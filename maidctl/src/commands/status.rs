@@ -0,0 +1,74 @@
+use crate::printer::Printer;
+use crate::service::{ServiceAction, ServiceManager};
+use maidono_core::control::{ControlRequest, ControlResponse, ServerStatus};
+use maidono_core::utils::path::CONTROL_SOCKET;
+use maidono_core::utils::ErrorPrinter;
+use owo_colors::OwoColorize;
+use std::path::Path;
+
+pub fn status() {
+    let mut printer = Printer::new();
+    let socket = Path::new(CONTROL_SOCKET);
+
+    match ControlRequest::Status.send(socket) {
+        Ok(ControlResponse::Status(status)) => {
+            print_status(&mut printer, status);
+        }
+        Ok(ControlResponse::Error { message }) => {
+            printer.print_error(message);
+            return;
+        }
+        Ok(_) => {
+            printer.print_error("Unexpected response from the server");
+            return;
+        }
+        Err(err) => {
+            let manager = match ServiceManager::from_config() {
+                Ok(manager) => manager,
+                Err(config_err) => {
+                    printer.print_warning("Unable to reach the server");
+                    printer.indent().print_detailed_error(&err).unindent();
+                    printer.print_detailed_error(&config_err);
+                    return;
+                }
+            };
+            printer.print_warning(format!(
+                "Unable to reach the server, checking {} instead",
+                manager
+            ));
+            printer.indent().print_detailed_error(&err).unindent();
+            if let Err(err) = manager.run(&mut printer, ServiceAction::Status) {
+                printer.print_detailed_error(&err);
+            }
+            return;
+        }
+    }
+
+    if let Ok(ControlResponse::Runs { runs }) = ControlRequest::ActiveRuns.send(socket) {
+        for run in runs {
+            printer
+                .indent()
+                .print_text("▸".blue())
+                .print_text("run")
+                .print_em_text(run.id)
+                .print_text("of")
+                .print_em_text(run.action)
+                .println_text(format!("started at {}", run.started_at))
+                .unindent();
+        }
+    }
+}
+
+fn print_status(printer: &mut Printer, status: ServerStatus) {
+    printer
+        .print_text("●".green())
+        .print_em_text(format!("maidono {}", status.version))
+        .println_text(format!("is running since {}", status.started_at))
+        .indent()
+        .println_text(format!(
+            "{} enabled action(s), loaded at {}",
+            status.enabled_actions, status.loaded_at
+        ))
+        .println_text(format!("{} run(s) in progress", status.runs_in_progress))
+        .unindent();
+}
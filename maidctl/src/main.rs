@@ -3,12 +3,18 @@ use clap::Parser;
 use maidono_core::enabled_actions::ActionStatus;
 use maidono_core::utils::ErrorPrinter;
 
-use crate::cli::{Cli, Commands};
-use crate::commands::{FileTestPayload, NoTestPayload, StringTestPayload};
+use crate::cli::{Cli, Commands, RunsCommands};
+use crate::commands::{
+    ExplainOptions, FileTestPayload, ListFilter, LogsOptions, NewActionOptions, NoTestPayload,
+    RunsFilter, StringTestPayload,
+};
+use crate::service::ServiceAction;
 
 mod cli;
 mod commands;
+mod output;
 mod printer;
+mod service;
 
 fn main() {
     let args = Cli::parse();
@@ -16,14 +22,45 @@ fn main() {
 
     match args.command {
         Commands::List {
+            pattern,
             enabled,
             disabled,
+            from,
             invalid,
-        } => commands::list(ActionStatus::from_flags(enabled, disabled), invalid),
+        } => commands::list(
+            ListFilter {
+                status: ActionStatus::from_flags(enabled, disabled),
+                pattern,
+                from,
+            },
+            invalid,
+            args.output,
+        ),
 
-        Commands::Show { group } => commands::show(group),
-        Commands::Enable { actions } => commands::enable(actions),
-        Commands::Disable { actions } => commands::disable(actions),
+        Commands::Show { group } => commands::show(group, args.output),
+        Commands::Edit { group } => commands::edit(group),
+        Commands::Enable { actions } => commands::enable(actions, args.output),
+        Commands::Disable { actions } => commands::disable(actions, args.output),
+        Commands::New {
+            path,
+            on,
+            from,
+            run,
+            secret,
+            enable,
+        } => commands::new(NewActionOptions {
+            path,
+            on,
+            from,
+            run,
+            secret,
+            enable,
+        }),
+        Commands::Graph { pattern, dot } => commands::graph(pattern, dot),
+        Commands::Check {
+            dir,
+            warnings_as_errors,
+        } => commands::check(dir, warnings_as_errors, args.output),
 
         Commands::Test {
             name_or_url,
@@ -53,10 +90,56 @@ fn main() {
             printer.print_error("both literal and file payload given");
         }
 
-        Commands::Status => commands::systemctl("status"),
+        Commands::Explain {
+            method,
+            url,
+            headers,
+            payload_file,
+        } => commands::explain(ExplainOptions {
+            method,
+            url,
+            headers,
+            payload_file,
+        }),
+
+        Commands::Logs {
+            pattern,
+            run,
+            follow,
+            failed,
+        } => commands::logs(LogsOptions {
+            pattern,
+            run,
+            follow,
+            failed,
+        }),
+
+        Commands::Runs {
+            command: Some(RunsCommands::Show { id }),
+            ..
+        } => commands::show_run(id, args.output),
+        Commands::Runs {
+            command: None,
+            pattern,
+            status,
+            since,
+            until,
+            limit,
+        } => commands::runs(
+            RunsFilter {
+                pattern,
+                status,
+                since,
+                until,
+                limit,
+            },
+            args.output,
+        ),
+
+        Commands::Status => commands::status(),
         Commands::Reload => commands::reload(),
-        Commands::Start => commands::systemctl("start"),
-        Commands::Stop => commands::systemctl("stop"),
-        Commands::Restart => commands::systemctl("restart"),
+        Commands::Start => commands::service(ServiceAction::Start),
+        Commands::Stop => commands::service(ServiceAction::Stop),
+        Commands::Restart => commands::service(ServiceAction::Restart),
     }
 }
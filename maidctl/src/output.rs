@@ -0,0 +1,42 @@
+use crate::printer::Printer;
+use clap::ValueEnum;
+use maidono_core::utils::{Error, ErrorPrinter};
+use serde::Serialize;
+
+/// How the commands print their results.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Colored text for humans
+    #[default]
+    Text,
+    /// A JSON document
+    Json,
+    /// A YAML document
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Prints a document in JSON or YAML, or calls `print_text` with the document in text mode.
+    pub fn print<T, F>(self, document: &T, print_text: F)
+    where
+        T: Serialize,
+        F: FnOnce(&mut Printer, &T),
+    {
+        let mut printer = Printer::new();
+        let serialized = match self {
+            OutputFormat::Text => {
+                print_text(&mut printer, document);
+                return;
+            }
+            OutputFormat::Json => serde_json::to_string_pretty(document).map_err(Error::from),
+            OutputFormat::Yaml => serde_yaml::to_string(document).map_err(Error::from),
+        };
+        match serialized {
+            Ok(text) => println!("{}", text.trim_end()),
+            Err(error) => {
+                printer.print_error("Unable to serialize the output");
+                printer.print_detailed_error(&error);
+            }
+        }
+    }
+}
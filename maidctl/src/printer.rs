@@ -22,12 +22,15 @@ macro_rules!
 
 use maidono_core::utils::{Error, ErrorPrinter};
 use owo_colors::{OwoColorize, Stream};
+use serde::{Serialize, Serializer};
 use std::fmt::Display;
 use std::fmt::Write;
 
 pub struct Printer {
     indent: usize,
     new_line: bool,
+    // errors are printed as warnings
+    warning: bool,
 }
 
 impl Printer {
@@ -35,6 +38,7 @@ impl Printer {
         Self {
             indent: 0,
             new_line: true,
+            warning: false,
         }
     }
 
@@ -99,15 +103,43 @@ impl Printer {
         self
     }
 
-    pub fn print_very_compact_error(&mut self, error: Error) -> &mut Self {
+    pub fn print_warning<D: Display>(&mut self, text: D) -> &mut Self {
+        eprintln!(
+            " {}{}",
+            self.pad(),
+            format!("Warning: {}", text).if_supports_color(Stream::Stderr, |x| x.yellow())
+        );
+        self.new_line = true;
+        self
+    }
+
+    pub fn print_very_compact_error(&mut self, error: &Error) -> &mut Self {
         error.display_very_compact(self);
         self
     }
 
-    pub fn print_detailed_error(&mut self, error: Error) -> &mut Self {
+    pub fn print_detailed_error(&mut self, error: &Error) -> &mut Self {
         error.display_detailed(self);
         self
     }
+
+    pub fn print_detailed_warning(&mut self, error: &Error) -> &mut Self {
+        self.warning = true;
+        self.print_error_inline("Warning:");
+        error.display_detailed(self);
+        self.warning = false;
+        self
+    }
+
+    fn error_style<D: Display>(&self, text: D) -> String {
+        if self.warning {
+            text.if_supports_color(Stream::Stderr, |x| x.yellow())
+                .to_string()
+        } else {
+            text.if_supports_color(Stream::Stderr, |x| x.bright_red())
+                .to_string()
+        }
+    }
 }
 
 pub struct SensitiveStr<'a> {
@@ -120,6 +152,12 @@ impl<'a> From<&'a str> for SensitiveStr<'a> {
     }
 }
 
+impl Serialize for SensitiveStr<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl Display for SensitiveStr<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&String::from("*").repeat(self.value.len()))
@@ -138,21 +176,13 @@ impl ErrorPrinter for Printer {
     }
 
     fn print_error<D: Display>(&mut self, text: D) -> &mut Self {
-        eprintln!(
-            " {}{}",
-            self.pad(),
-            text.if_supports_color(Stream::Stderr, |x| x.bright_red())
-        );
+        eprintln!(" {}{}", self.pad(), self.error_style(text));
         self.new_line = true;
         self
     }
 
     fn print_error_inline<D: Display>(&mut self, text: D) -> &mut Self {
-        eprint!(
-            " {}{}",
-            self.pad(),
-            text.if_supports_color(Stream::Stderr, |x| x.bright_red())
-        );
+        eprint!(" {}{}", self.pad(), self.error_style(text));
         self.new_line = false;
         self
     }
@@ -0,0 +1,170 @@
+//! The service managers that can start and stop the server.
+
+mod pid_file;
+
+use crate::printer::Printer;
+use maidono_core::problem;
+use maidono_core::utils::path::SERVER_CONFIG_FILE;
+use maidono_core::utils::Result;
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The name of the service in the service managers.
+const SERVICE_NAME: &str = "maidono";
+
+/// Where runit looks for the services, depending on the distribution.
+const RUNIT_SERVICE_DIRS: [&str; 3] = ["/etc/service", "/var/service", "/service"];
+
+#[derive(Debug, Clone, Copy)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+    Status,
+}
+
+#[derive(Debug)]
+pub enum ServiceManager {
+    Systemd,
+    OpenRc,
+    Runit(PathBuf),
+    /// No service manager, `maidctl` runs the server in the background itself and keeps its PID
+    /// in a file.
+    PidFile,
+}
+
+/// The `service_manager` key of the server config.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ServiceManagerKind {
+    Auto,
+    Systemd,
+    OpenRc,
+    Runit,
+    PidFile,
+}
+
+#[derive(Deserialize)]
+struct ServiceConfig {
+    service_manager: Option<ServiceManagerKind>,
+}
+
+impl ServiceManager {
+    /// The service manager set in the server config, or the one used by the system.
+    pub fn from_config() -> Result<Self> {
+        let kind = match read_to_string(SERVER_CONFIG_FILE) {
+            Ok(source) => toml::from_str::<ServiceConfig>(&source)
+                .map_err(|err| {
+                    problem!("Unable to read {}", SERVER_CONFIG_FILE)
+                        .because(problem!("{}", err.message()))
+                })?
+                .service_manager
+                .unwrap_or(ServiceManagerKind::Auto),
+            Err(err) if err.kind() == ErrorKind::NotFound => ServiceManagerKind::Auto,
+            Err(err) => {
+                return Err(problem!("Unable to read {}", SERVER_CONFIG_FILE).because(err));
+            }
+        };
+
+        Ok(match kind {
+            ServiceManagerKind::Auto => Self::detect(),
+            ServiceManagerKind::Systemd => Self::Systemd,
+            ServiceManagerKind::OpenRc => Self::OpenRc,
+            ServiceManagerKind::Runit => Self::Runit(find_runit_service().ok_or(problem!(
+                "The maidono service was not found in the runit service directories"
+            ))?),
+            ServiceManagerKind::PidFile => Self::PidFile,
+        })
+    }
+
+    /// Guesses the service manager from what is running. If there is none (like in a container),
+    /// the server is started without one.
+    fn detect() -> Self {
+        if Path::new("/run/systemd/system").is_dir() {
+            Self::Systemd
+        } else if Path::new("/run/openrc").is_dir() {
+            Self::OpenRc
+        } else if let Some(service_dir) = find_runit_service() {
+            Self::Runit(service_dir)
+        } else {
+            Self::PidFile
+        }
+    }
+
+    /// Does something with the service. The external service managers print their own output.
+    pub fn run(&self, printer: &mut Printer, action: ServiceAction) -> Result<()> {
+        match self {
+            Self::Systemd => run_command(Command::new("systemctl").args([
+                match action {
+                    ServiceAction::Start => "start",
+                    ServiceAction::Stop => "stop",
+                    ServiceAction::Restart => "restart",
+                    ServiceAction::Status => "status",
+                },
+                SERVICE_NAME,
+            ])),
+            Self::OpenRc => run_command(Command::new("rc-service").args([
+                SERVICE_NAME,
+                match action {
+                    ServiceAction::Start => "start",
+                    ServiceAction::Stop => "stop",
+                    ServiceAction::Restart => "restart",
+                    ServiceAction::Status => "status",
+                },
+            ])),
+            Self::Runit(service_dir) => run_command(
+                Command::new("sv")
+                    .arg(match action {
+                        ServiceAction::Start => "up",
+                        ServiceAction::Stop => "down",
+                        ServiceAction::Restart => "restart",
+                        ServiceAction::Status => "status",
+                    })
+                    .arg(service_dir),
+            ),
+            Self::PidFile => match action {
+                ServiceAction::Start => pid_file::start(printer),
+                ServiceAction::Stop => pid_file::stop(printer),
+                ServiceAction::Restart => {
+                    pid_file::stop(printer).and_then(|_| pid_file::start(printer))
+                }
+                ServiceAction::Status => pid_file::status(printer),
+            },
+        }
+    }
+}
+
+impl Display for ServiceManager {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Systemd => write!(f, "systemd"),
+            Self::OpenRc => write!(f, "OpenRC"),
+            Self::Runit(_) => write!(f, "runit"),
+            Self::PidFile => write!(f, "the PID file"),
+        }
+    }
+}
+
+fn find_runit_service() -> Option<PathBuf> {
+    RUNIT_SERVICE_DIRS
+        .iter()
+        .map(|dir| Path::new(dir).join(SERVICE_NAME))
+        .find(|service_dir| service_dir.is_dir())
+}
+
+fn run_command(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .status()
+        .map_err(|err| problem!("Unable to use {}", program).because(err))?;
+
+    match (status.success(), status.code()) {
+        (true, _) => Ok(()),
+        (false, Some(code)) => Err(problem!("{} exited with status code {}", program, code)),
+        (false, None) => Err(problem!("{} was terminated by a signal", program)),
+    }
+}
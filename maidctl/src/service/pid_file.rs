@@ -0,0 +1,151 @@
+use crate::printer::Printer;
+use maidono_core::problem;
+use maidono_core::utils::path::{RUNTIME_DIR, SERVER_LOG_FILE, SERVER_PID_FILE};
+use maidono_core::utils::Result;
+use owo_colors::OwoColorize;
+use std::env::current_exe;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// How long to wait before checking that the server didn't exit right away (it does if its config
+/// is invalid).
+const STARTUP_DELAY: Duration = Duration::from_millis(500);
+
+/// How long the server is given to finish the requests in progress when it is stopped.
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn start(printer: &mut Printer) -> Result<()> {
+    if let Some(pid) = running_server()? {
+        return Err(problem!("The server is already running with PID {}", pid));
+    }
+
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(SERVER_LOG_FILE)
+        .map_err(|err| problem!("Unable to open {}", SERVER_LOG_FILE).because(err))?;
+    let executable = server_executable();
+    let mut child = Command::new(&executable)
+        .current_dir(RUNTIME_DIR)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        // keeps the server running when the terminal is closed
+        .process_group(0)
+        .spawn()
+        .map_err(|err| problem!("Unable to start {}", executable.display()).because(err))?;
+
+    let pid = child.id();
+    if let Err(err) = fs::write(SERVER_PID_FILE, format!("{}\n", pid)) {
+        let _ = child.kill();
+        return Err(problem!("Unable to write {}", SERVER_PID_FILE).because(err));
+    }
+
+    sleep(STARTUP_DELAY);
+    if let Some(status) = child.try_wait()? {
+        let _ = fs::remove_file(SERVER_PID_FILE);
+        return Err(problem!(
+            "The server stopped right after starting ({}), see {}",
+            status,
+            SERVER_LOG_FILE
+        ));
+    }
+
+    printer
+        .print_text("●".green())
+        .print_text("Started the server with PID")
+        .print_em_text(pid)
+        .println_text(format!("(its output goes to {})", SERVER_LOG_FILE).dimmed());
+    Ok(())
+}
+
+pub fn stop(printer: &mut Printer) -> Result<()> {
+    let Some(pid) = running_server()? else {
+        remove_pid_file()?;
+        printer
+            .print_text("○")
+            .println_text("The server is not running");
+        return Ok(());
+    };
+
+    let status = Command::new("kill")
+        .arg(pid.to_string())
+        .status()
+        .map_err(|err| problem!("Unable to stop the server").because(err))?;
+    if !status.success() {
+        return Err(problem!("Unable to stop the server with PID {}", pid));
+    }
+
+    let asked_at = Instant::now();
+    while is_server(pid) {
+        if asked_at.elapsed() > STOP_TIMEOUT {
+            return Err(problem!(
+                "The server with PID {} did not stop within {} seconds",
+                pid,
+                STOP_TIMEOUT.as_secs()
+            ));
+        }
+        sleep(Duration::from_millis(100));
+    }
+    remove_pid_file()?;
+
+    printer
+        .print_text("○")
+        .print_text("Stopped the server with PID")
+        .print_em_text(pid)
+        .println();
+    Ok(())
+}
+
+pub fn status(printer: &mut Printer) -> Result<()> {
+    match running_server()? {
+        Some(pid) => printer
+            .print_text("●".green())
+            .print_text("The server is running with PID")
+            .print_em_text(pid)
+            .println_text(format!("(its output goes to {})", SERVER_LOG_FILE).dimmed()),
+        None => printer
+            .print_text("○")
+            .println_text("The server is not running"),
+    };
+    Ok(())
+}
+
+/// The PID of the server started by `maidctl start`, if it is still running.
+fn running_server() -> Result<Option<u32>> {
+    let content = match fs::read_to_string(SERVER_PID_FILE) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(problem!("Unable to read {}", SERVER_PID_FILE).because(err)),
+    };
+    Ok(content.trim().parse().ok().filter(|pid| is_server(*pid)))
+}
+
+/// Whether a process is running the server, which it may not be anymore if the PID was reused.
+fn is_server(pid: u32) -> bool {
+    fs::read_to_string(Path::new("/proc").join(pid.to_string()).join("comm"))
+        .is_ok_and(|name| name.trim() == "maidono")
+}
+
+fn remove_pid_file() -> Result<()> {
+    match fs::remove_file(SERVER_PID_FILE) {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            Err(problem!("Unable to remove {}", SERVER_PID_FILE).because(err))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The `maidono` executable installed next to `maidctl`, or the one on the PATH.
+fn server_executable() -> PathBuf {
+    current_exe()
+        .ok()
+        .map(|maidctl| maidctl.with_file_name("maidono"))
+        .filter(|maidono| maidono.is_file())
+        .unwrap_or_else(|| PathBuf::from("maidono"))
+}
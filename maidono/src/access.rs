@@ -0,0 +1,57 @@
+use rocket::Request;
+use subtle::ConstantTimeEq;
+
+/// The result of checking the bearer token of a request against a setting like `admin_token`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum TokenCheck {
+    /// The setting is missing.
+    Unset,
+    Valid,
+    /// The token is missing or doesn't match the setting.
+    Invalid,
+}
+
+impl TokenCheck {
+    pub(crate) fn of(req: &Request<'_>, setting: &str) -> Self {
+        match req.rocket().figment().extract_inner::<String>(setting) {
+            Ok(expected) => Self::compare(req.headers().get_one("Authorization"), &expected),
+            Err(_) => Self::Unset,
+        }
+    }
+
+    fn compare(authorization: Option<&str>, expected: &str) -> Self {
+        let given = authorization.and_then(|value| value.strip_prefix("Bearer "));
+        // the tokens are compared in constant time, so that they can't be guessed from the
+        // response times
+        match given {
+            Some(token) if bool::from(token.as_bytes().ct_eq(expected.as_bytes())) => Self::Valid,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare() {
+        assert_eq!(
+            TokenCheck::compare(Some("Bearer secret"), "secret"),
+            TokenCheck::Valid
+        );
+        assert_eq!(
+            TokenCheck::compare(Some("Bearer other"), "secret"),
+            TokenCheck::Invalid
+        );
+        assert_eq!(
+            TokenCheck::compare(Some("Bearer secret2"), "secret"),
+            TokenCheck::Invalid
+        );
+        assert_eq!(
+            TokenCheck::compare(Some("secret"), "secret"),
+            TokenCheck::Invalid
+        );
+        assert_eq!(TokenCheck::compare(None, "secret"), TokenCheck::Invalid);
+    }
+}
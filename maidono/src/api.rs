@@ -0,0 +1,369 @@
+use crate::access::TokenCheck;
+use crate::handler::{start_run, BaseUrl, RunResponse, ServerState};
+use crate::logger::Logger;
+use crate::state::{LoadedActions, Metrics, RunRecord, Runs};
+use maidono_core::actions::{
+    find_group_file, read_group_by_name, try_read_all_groups, Action, Dependencies, ExecutionPlan,
+    Group, TriggerParameters,
+};
+use maidono_core::enabled_actions::{dump_enabled_actions, load_enabled_actions, EnabledList};
+use maidono_core::problem;
+use maidono_core::utils::{ActionPath, Error};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder};
+use rocket::serde::json::{self, to_value, Json, Value};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::{get, post, routes, Request, Route, State};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+pub(crate) fn routes() -> Vec<Route> {
+    routes![
+        list_groups,
+        list_actions,
+        show_action,
+        enable_action,
+        disable_action,
+        run_action,
+        list_runs,
+        show_run
+    ]
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct GroupsResponse {
+    groups: BTreeMap<String, Group>,
+    invalid_groups: BTreeMap<String, String>,
+    #[serde(flatten)]
+    enabled: EnabledList,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ActionResponse<'a> {
+    path: &'a ActionPath,
+    enabled: bool,
+    #[serde(flatten)]
+    action: &'a Action,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain: Option<ExecutionPlan>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain_error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct EnabledStateResponse {
+    path: ActionPath,
+    enabled: bool,
+    changed: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+struct RunRequest {
+    #[serde(default)]
+    parameters: TriggerParameters,
+}
+
+/// Lists the groups as they are in the config directory, and the enabled actions.
+#[get("/groups")]
+fn list_groups(_access: AdminAccess) -> ApiResult<GroupsResponse> {
+    let mut response = GroupsResponse {
+        groups: BTreeMap::new(),
+        invalid_groups: BTreeMap::new(),
+        enabled: load_enabled_actions()?,
+    };
+    for (name, result) in try_read_all_groups()? {
+        match result {
+            Ok(group) => {
+                response.groups.insert(name, group);
+            }
+            Err(error) => {
+                response.invalid_groups.insert(name, error.to_string());
+            }
+        }
+    }
+    Ok(Json(response))
+}
+
+/// Lists the actions of all the valid groups, with their enabled state.
+#[get("/actions")]
+fn list_actions(_access: AdminAccess) -> ApiResult<Value> {
+    let enabled = load_enabled_actions()?;
+    let mut actions: Vec<(ActionPath, Action)> = try_read_all_groups()?
+        .into_iter()
+        .filter_map(|(name, result)| result.ok().map(|group| (name, group)))
+        .flat_map(|(group_name, group)| {
+            group.into_enumerated_actions().map(move |(name, action)| {
+                (ActionPath::from_parts(group_name.clone(), name), action)
+            })
+        })
+        .collect();
+    actions.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    let response: Vec<ActionResponse> = actions
+        .iter()
+        .map(|(path, action)| ActionResponse {
+            path,
+            enabled: enabled.is_path_enabled(path),
+            action,
+            chain: None,
+            chain_error: None,
+        })
+        .collect();
+    serialize_borrowed(response)
+}
+
+/// Shows an action with the chain of actions it runs.
+#[get("/actions/<group>/<name>")]
+fn show_action(_access: AdminAccess, group: &str, name: &str) -> ApiResult<Value> {
+    let path = ActionPath::from_parts(group, name);
+    let enabled = load_enabled_actions()?;
+    let groups: Vec<(String, Group)> = try_read_all_groups()?
+        .into_iter()
+        .filter_map(|(name, result)| result.ok().map(|group| (name, group)))
+        .collect();
+
+    let action = groups
+        .iter()
+        .find(|(group_name, _)| group_name == group)
+        .and_then(|(_, group)| group.action(name))
+        .ok_or_else(|| ApiError::not_found(&path))?;
+    let dependencies =
+        Dependencies::from_groups(groups.iter().map(|(name, group)| (name.as_str(), group)));
+    let (chain, chain_error) = match dependencies.plan(&path) {
+        Ok(plan) => (Some(plan), None),
+        Err(error) => (None, Some(error.to_string())),
+    };
+
+    let response = ActionResponse {
+        path: &path,
+        enabled: enabled.is_path_enabled(&path),
+        action,
+        chain,
+        chain_error,
+    };
+    serialize_borrowed(response)
+}
+
+/// Serializes a response that borrows from values which don't outlive the route.
+fn serialize_borrowed<T: Serialize>(response: T) -> ApiResult<Value> {
+    to_value(response).map(Json).map_err(|error| {
+        ApiError::from(
+            problem!("Could not serialize the response").because(error.to_string().as_str()),
+        )
+    })
+}
+
+#[post("/actions/<group>/<name>/enable")]
+fn enable_action(
+    _access: AdminAccess,
+    group: &str,
+    name: &str,
+    logger: Logger,
+    services: Services<'_>,
+) -> ApiResult<EnabledStateResponse> {
+    set_enabled_state(ActionPath::from_parts(group, name), true, &logger, services)
+}
+
+#[post("/actions/<group>/<name>/disable")]
+fn disable_action(
+    _access: AdminAccess,
+    group: &str,
+    name: &str,
+    logger: Logger,
+    services: Services<'_>,
+) -> ApiResult<EnabledStateResponse> {
+    set_enabled_state(
+        ActionPath::from_parts(group, name),
+        false,
+        &logger,
+        services,
+    )
+}
+
+/// Held while the enabled actions list is read, changed and written back, so that concurrent
+/// changes are not lost.
+static ENABLED_LIST_WRITER: Mutex<()> = Mutex::new(());
+
+/// Updates the enabled actions list, and reloads the actions so that it takes effect.
+fn set_enabled_state(
+    path: ActionPath,
+    enabled: bool,
+    logger: &Logger,
+    services: Services<'_>,
+) -> ApiResult<EnabledStateResponse> {
+    let (group, name) = path.clone().into_parts();
+    if find_group_file(&group)?.is_none() || read_group_by_name(&group)?.action(&name).is_none() {
+        return Err(ApiError::not_found(&path));
+    }
+
+    let _writer = ENABLED_LIST_WRITER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut list = load_enabled_actions()?;
+    let changed = if enabled {
+        list.enable_path(path.clone())
+    } else {
+        list.disable_path(path.clone())
+    };
+    if changed {
+        dump_enabled_actions(&list)?;
+        services
+            .loaded
+            .reload(services.metrics, logger)
+            .map_err(|error| {
+                problem!("The enabled actions were updated, but the actions could not be reloaded")
+                    .because(error)
+            })?;
+    }
+
+    Ok(Json(EnabledStateResponse {
+        path,
+        enabled,
+        changed,
+    }))
+}
+
+/// Runs an action without checking its origin or its secret, even if it is disabled.
+#[post("/actions/<group>/<name>/run", data = "<request>")]
+fn run_action(
+    _access: AdminAccess,
+    group: &str,
+    name: &str,
+    request: Result<Json<RunRequest>, json::Error<'_>>,
+    logger: Logger,
+    services: Services<'_>,
+    base_url: BaseUrl,
+) -> Result<(Status, Json<RunResponse>), ApiError> {
+    let path = ActionPath::from_parts(group, name);
+    let actions = services.loaded.get();
+    let action_ref = actions
+        .get(&path)
+        .ok_or_else(|| ApiError::not_found(&path))?;
+    let parameters = match request {
+        Ok(json) => json.into_inner().parameters,
+        // the body is optional, but it must be valid when there is one
+        Err(json::Error::Parse(body, _)) if body.trim().is_empty() => TriggerParameters::new(),
+        Err(error) => {
+            return Err(ApiError::new(
+                Status::BadRequest,
+                format!("Invalid request: {}", error),
+            ))
+        }
+    };
+
+    let logger = logger.for_action(&path);
+    logger.log(format!("Action '{}' triggered through the API", path));
+
+    let ctx = actions.load_context_for(action_ref, parameters)?;
+    let state = ServerState {
+        actions: &actions,
+        runs: services.runs,
+        metrics: services.metrics,
+    };
+    let (record, _) = start_run(&logger, state, &path, ctx);
+    Ok((Status::Accepted, Json(RunResponse::new(&base_url, record))))
+}
+
+#[get("/runs")]
+fn list_runs(_access: AdminAccess, runs: &State<Runs>) -> Json<Vec<RunRecord>> {
+    Json(runs.list())
+}
+
+#[get("/runs/<id>")]
+fn show_run(_access: AdminAccess, id: &str, runs: &State<Runs>) -> ApiResult<RunRecord> {
+    runs.get(id)
+        .map(Json)
+        .ok_or_else(|| ApiError::new(Status::NotFound, format!("No run with ID '{}'", id)))
+}
+
+/// Checks the bearer token of the request against the `admin_token` setting. The API is
+/// disabled if there is no such setting.
+struct AdminAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminAccess {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match TokenCheck::of(req, "admin_token") {
+            TokenCheck::Valid => Outcome::Success(AdminAccess),
+            TokenCheck::Invalid => Outcome::Error((Status::Unauthorized, ())),
+            TokenCheck::Unset => Outcome::Error((Status::Forbidden, ())),
+        }
+    }
+}
+
+/// The managed state used by the API.
+struct Services<'r> {
+    loaded: &'r LoadedActions,
+    runs: &'r Runs,
+    metrics: &'r Metrics,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Services<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let rocket = req.rocket();
+        match (
+            rocket.state::<LoadedActions>(),
+            rocket.state::<Runs>(),
+            rocket.state::<Metrics>(),
+        ) {
+            (Some(loaded), Some(runs), Some(metrics)) => Outcome::Success(Services {
+                loaded,
+                runs,
+                metrics,
+            }),
+            _ => Outcome::Error((Status::ServiceUnavailable, ())),
+        }
+    }
+}
+
+/// An error, responded as `{ "error": "..." }`.
+struct ApiError {
+    status: Status,
+    message: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ErrorBody {
+    error: String,
+}
+
+impl ApiError {
+    fn new(status: Status, message: String) -> Self {
+        Self { status, message }
+    }
+
+    fn not_found(path: &ActionPath) -> Self {
+        Self::new(Status::NotFound, format!("Action '{}' not found", path))
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(error: Error) -> Self {
+        Self::new(Status::InternalServerError, error.to_string())
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        (
+            self.status,
+            Json(ErrorBody {
+                error: self.message,
+            }),
+        )
+            .respond_to(request)
+    }
+}
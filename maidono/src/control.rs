@@ -0,0 +1,446 @@
+use crate::handler::{start_run, ServerState};
+use crate::logger::{log_format, Logger};
+use crate::state::{LoadedActions, Metrics, RunRecord, Runs};
+use crate::version;
+use chrono::{DateTime, SecondsFormat, Utc};
+use maidono_core::actions::TriggerParameters;
+use maidono_core::control::{
+    decode_message, encode_message, ControlRequest, ControlResponse, RunSummary, ServerStatus,
+};
+use maidono_core::problem;
+use maidono_core::utils::path::CONTROL_SOCKET;
+use maidono_core::utils::{ActionPath, Error, Result};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{async_trait, Orbit, Rocket};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+/// The group that can use the control socket besides the user running the server.
+const SOCKET_GROUP: &str = "maidono";
+
+/// Listens on a Unix socket for the requests of `maidctl`, once the server is started.
+pub struct ControlSocket();
+
+#[async_trait]
+impl Fairing for ControlSocket {
+    fn info(&self) -> Info {
+        Info {
+            kind: Kind::Liftoff | Kind::Shutdown,
+            name: "Control socket",
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let logger = Logger::new(rocket.config().log_level, log_format(rocket.figment()));
+        let (Some(loaded), Some(runs), Some(metrics)) = (
+            rocket.state::<LoadedActions>(),
+            rocket.state::<Runs>(),
+            rocket.state::<Metrics>(),
+        ) else {
+            return;
+        };
+
+        let path = Path::new(CONTROL_SOCKET);
+        match bind(path, &logger) {
+            Ok(listener) => {
+                logger.debug_message(format!("Listening on {}", path.display()));
+                let controller = Controller {
+                    loaded: loaded.clone(),
+                    runs: runs.clone(),
+                    metrics: metrics.clone(),
+                    logger,
+                    started_at: Utc::now(),
+                };
+                tokio::spawn(accept_connections(listener, controller));
+            }
+            Err(error) => {
+                logger.warning_message(
+                    "Unable to open the control socket, maidctl won't be able to reach the server",
+                );
+                logger.error(error);
+            }
+        }
+    }
+
+    async fn on_shutdown(&self, _rocket: &Rocket<Orbit>) {
+        // a socket left behind would make maidctl believe that the server is still running
+        _ = std::fs::remove_file(CONTROL_SOCKET);
+    }
+}
+
+fn bind(path: &Path, logger: &Logger) -> Result<UnixListener> {
+    // a socket file remains if the server was killed
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    // only the maidono user and group can control the server, so the socket is created without
+    // any access for the others instead of being restricted once anyone could have connected
+    // SAFETY: umask only changes the mode of the files created by the process
+    let previous_umask = unsafe { libc::umask(0o117) };
+    let bound = UnixListener::bind(path);
+    // SAFETY: see above
+    unsafe {
+        libc::umask(previous_umask);
+    }
+    let listener =
+        bound.map_err(|error| problem!("Unable to bind to {}", path.display()).because(error))?;
+
+    match group_id(SOCKET_GROUP) {
+        Some(gid) => {
+            if let Err(error) = std::os::unix::fs::chown(path, None, Some(gid)) {
+                logger.warning_message(format!(
+                    "Unable to give {} to the '{}' group: {}",
+                    path.display(),
+                    SOCKET_GROUP,
+                    error
+                ));
+            }
+        }
+        None => logger.warning_message(format!(
+            "There is no '{}' group, only the user running the server and its group can use {}",
+            SOCKET_GROUP,
+            path.display()
+        )),
+    }
+    Ok(listener)
+}
+
+/// Looks up the ID of a group in `/etc/group`.
+fn group_id(name: &str) -> Option<u32> {
+    let groups = std::fs::read_to_string("/etc/group").ok()?;
+    groups.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        if fields.next()? != name {
+            return None;
+        }
+        fields.nth(1)?.parse().ok()
+    })
+}
+
+async fn accept_connections(listener: UnixListener, controller: Controller) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(controller.clone().serve(stream));
+            }
+            Err(error) => {
+                controller
+                    .logger
+                    .error_message(format!("Control socket connection failed: {}", error));
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Controller {
+    loaded: LoadedActions,
+    runs: Runs,
+    metrics: Metrics,
+    logger: Logger,
+    started_at: DateTime<Utc>,
+}
+
+impl Controller {
+    async fn serve(self, stream: UnixStream) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            let response = match decode_message(&line) {
+                Ok(request) => self.handle(request),
+                Err(error) => error_response(error),
+            };
+            let Ok(encoded) = encode_message(&response) else {
+                break;
+            };
+            if writer.write_all(encoded.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    fn handle(&self, request: ControlRequest) -> ControlResponse {
+        let result = match request {
+            ControlRequest::Status => Ok(self.status()),
+            ControlRequest::Reload => self.reload(),
+            ControlRequest::ActiveRuns => Ok(ControlResponse::Runs {
+                runs: self.runs.in_progress().iter().map(summarize).collect(),
+            }),
+            ControlRequest::History => Ok(ControlResponse::Runs {
+                runs: self.runs.list().iter().map(summarize).collect(),
+            }),
+            ControlRequest::Run { run } => self.run(&run),
+            ControlRequest::RunLog { run, from } => self.run_log(&run, from),
+            ControlRequest::Cancel { run } => self.cancel(&run),
+            ControlRequest::Trigger { action, parameters } => self.trigger(&action, parameters),
+        };
+        result.unwrap_or_else(error_response)
+    }
+
+    fn status(&self) -> ControlResponse {
+        let actions = self.loaded.get();
+        ControlResponse::Status(ServerStatus {
+            version: format!("{}.{}.{}", version::MAJOR, version::MINOR, version::PATCH),
+            started_at: timestamp(self.started_at),
+            loaded_at: timestamp(actions.loaded_at()),
+            enabled_actions: actions.enabled_count(),
+            runs_in_progress: self.metrics.runs_in_progress(),
+        })
+    }
+
+    fn reload(&self) -> Result<ControlResponse> {
+        let actions = self.loaded.reload(&self.metrics, &self.logger)?;
+        self.logger
+            .log("Actions reloaded through the control socket");
+        Ok(ControlResponse::Reloaded {
+            enabled_actions: actions.enabled_count(),
+        })
+    }
+
+    fn run(&self, id: &str) -> Result<ControlResponse> {
+        let record = self
+            .runs
+            .get(id)
+            .ok_or_else(|| problem!("No run with ID '{}'", id))?;
+        Ok(ControlResponse::Run {
+            run: summarize(&record),
+            actions: record.report().outcomes(),
+        })
+    }
+
+    fn run_log(&self, id: &str, from: usize) -> Result<ControlResponse> {
+        let (record, entries) = self
+            .runs
+            .log(id, from)
+            .ok_or_else(|| problem!("No run with ID '{}'", id))?;
+        Ok(ControlResponse::RunLog {
+            run: summarize(&record),
+            entries,
+        })
+    }
+
+    fn cancel(&self, id: &str) -> Result<ControlResponse> {
+        let record = self
+            .runs
+            .cancel(id, &self.metrics)
+            .ok_or_else(|| problem!("No run in progress with ID '{}'", id))?;
+        self.logger
+            .for_action(record.action())
+            .for_run(record.id())
+            .warning_message(format!("Run {} was cancelled", record.id()));
+        Ok(ControlResponse::Cancelled {
+            run: summarize(&record),
+        })
+    }
+
+    fn trigger(&self, action: &str, parameters: TriggerParameters) -> Result<ControlResponse> {
+        let path = ActionPath::parse(action)?;
+        let actions = self.loaded.get();
+        let action_ref = actions
+            .get(&path)
+            .ok_or_else(|| problem!("No action named '{}'", path))?;
+
+        let logger = self.logger.for_action(&path);
+        logger.log(format!(
+            "Action '{}' triggered through the control socket",
+            path
+        ));
+
+        let ctx = actions.load_context_for(action_ref, parameters)?;
+        let state = ServerState {
+            actions: &actions,
+            runs: &self.runs,
+            metrics: &self.metrics,
+        };
+        let (record, _) = start_run(&logger, state, &path, ctx);
+        Ok(ControlResponse::Triggered {
+            run: summarize(&record),
+        })
+    }
+}
+
+fn error_response(error: Error) -> ControlResponse {
+    ControlResponse::Error {
+        message: error.to_string().trim_start().to_owned(),
+    }
+}
+
+fn summarize(record: &RunRecord) -> RunSummary {
+    RunSummary {
+        id: record.id().to_owned(),
+        action: record.action().to_owned(),
+        chain: record.chain().to_vec(),
+        status: record.status().name().to_owned(),
+        started_at: timestamp(record.started_at()),
+        finished_at: record.finished_at().map(timestamp),
+        duration_ms: record
+            .duration()
+            .map(|duration| duration.as_millis().try_into().unwrap_or(u64::MAX)),
+        delivery: record.delivery().map(str::to_owned),
+    }
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::{RunReport, RunStatus};
+    use crate::logger::LogFormat;
+    use crate::state::Actions;
+    use maidono_core::actions::{parse_group, ActionIndex};
+    use maidono_core::enabled_actions::EnabledList;
+    use rocket::config::LogLevel;
+
+    fn controller() -> Controller {
+        let group =
+            parse_group("site.yml", "- name: build\n  on: /build\n  run: 'true'\n").unwrap();
+        let mut enabled = EnabledList::default();
+        enabled.enable_path(ActionPath::from_parts("site", "build"));
+        let index = ActionIndex::build([(String::from("site"), group)], enabled).unwrap();
+
+        Controller {
+            loaded: LoadedActions::new(Actions::new(index)),
+            runs: Runs::default(),
+            metrics: Metrics::default(),
+            logger: Logger::new(LogLevel::Off, LogFormat::Json),
+            started_at: Utc::now(),
+        }
+    }
+
+    fn error_message(response: ControlResponse) -> String {
+        match response {
+            ControlResponse::Error { message } => message,
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn status() {
+        let controller = controller();
+        let ControlResponse::Status(status) = controller.handle(ControlRequest::Status) else {
+            panic!("expected the status");
+        };
+        assert_eq!(status.enabled_actions, 1);
+        assert_eq!(status.runs_in_progress, 0);
+    }
+
+    #[test]
+    fn unknown_runs() {
+        let controller = controller();
+        let message = error_message(controller.handle(ControlRequest::Run {
+            run: String::from("nope"),
+        }));
+        assert_eq!(message, "No run with ID 'nope'");
+        let message = error_message(controller.handle(ControlRequest::RunLog {
+            run: String::from("nope"),
+            from: 0,
+        }));
+        assert_eq!(message, "No run with ID 'nope'");
+        let message = error_message(controller.handle(ControlRequest::Cancel {
+            run: String::from("nope"),
+        }));
+        assert_eq!(message, "No run in progress with ID 'nope'");
+    }
+
+    #[test]
+    fn unknown_action() {
+        let controller = controller();
+        let message = error_message(controller.handle(ControlRequest::Trigger {
+            action: String::from("site/deploy"),
+            parameters: TriggerParameters::new(),
+        }));
+        assert_eq!(message, "No action named 'site/deploy'");
+    }
+
+    #[tokio::test]
+    async fn cancel() {
+        let controller = controller();
+        let actions = controller.loaded.get();
+        let path = ActionPath::from_parts("site", "build");
+        let context = actions
+            .load_context_for(actions.get(&path).unwrap(), TriggerParameters::new())
+            .unwrap();
+        let record = controller.runs.start(&path, &context);
+        controller.metrics.run_started(record.action());
+        let task = tokio::spawn(std::future::pending::<()>());
+        controller.runs.attach(record.id(), task.abort_handle());
+
+        let ControlResponse::Runs { runs } = controller.handle(ControlRequest::ActiveRuns) else {
+            panic!("expected the runs in progress");
+        };
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].chain, ["site/build"]);
+
+        let ControlResponse::Cancelled { run } = controller.handle(ControlRequest::Cancel {
+            run: record.id().to_owned(),
+        }) else {
+            panic!("expected the cancelled run");
+        };
+        assert_eq!(run.status, "cancelled");
+        assert!(run.finished_at.is_some());
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert_eq!(controller.metrics.runs_in_progress(), 0);
+
+        // the run can't be cancelled twice, even if it finishes meanwhile
+        controller
+            .runs
+            .finish(record.clone(), RunReport::new(), &controller.metrics);
+        let message = error_message(controller.handle(ControlRequest::Cancel {
+            run: record.id().to_owned(),
+        }));
+        assert_eq!(
+            message,
+            format!("No run in progress with ID '{}'", record.id())
+        );
+        let ControlResponse::Run { run, .. } = controller.handle(ControlRequest::Run {
+            run: record.id().to_owned(),
+        }) else {
+            panic!("expected the run");
+        };
+        assert_eq!(run.status, RunStatus::Cancelled.name());
+    }
+
+    #[tokio::test]
+    async fn trigger() {
+        let controller = controller();
+        let ControlResponse::Triggered { run } = controller.handle(ControlRequest::Trigger {
+            action: String::from("site/build"),
+            parameters: TriggerParameters::new(),
+        }) else {
+            panic!("expected the triggered run");
+        };
+        assert_eq!(run.action, "site/build");
+
+        let ControlResponse::Runs { runs } = controller.handle(ControlRequest::History) else {
+            panic!("expected the history");
+        };
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, run.id);
+    }
+
+    #[tokio::test]
+    async fn serve() {
+        let (client, server) = UnixStream::pair().unwrap();
+        tokio::spawn(controller().serve(server));
+        let (reader, mut writer) = client.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        writer.write_all(b"not json\n").await.unwrap();
+        let response: ControlResponse =
+            decode_message(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert!(error_message(response).starts_with("Invalid control message"));
+
+        // the connection is still usable after an invalid message
+        let request = encode_message(&ControlRequest::Status).unwrap();
+        writer.write_all(request.as_bytes()).await.unwrap();
+        let response: ControlResponse =
+            decode_message(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert!(matches!(response, ControlResponse::Status(_)));
+    }
+}
@@ -1,9 +1,12 @@
 use crate::state::ActionRef;
-use maidono_core::actions::Commands;
+use maidono_core::actions::{parameter_variable, Commands, TriggerParameters};
 use maidono_core::utils::ActionPath;
 
 pub struct Context {
     actions: Vec<ContextAction>,
+    parameters: TriggerParameters,
+    share_output: bool,
+    delivery: Option<String>,
 }
 
 pub struct ContextAction {
@@ -12,17 +15,50 @@ pub struct ContextAction {
 }
 
 impl Context {
-    pub fn actions(&self) -> &[ContextAction] {
-        &self.actions
+    pub fn new(actions: Vec<ActionRef<'_>>, parameters: TriggerParameters) -> Self {
+        Self {
+            actions: actions.iter().map(|action_ref| action_ref.into()).collect(),
+            parameters,
+            share_output: false,
+            delivery: None,
+        }
     }
-}
 
-impl From<Vec<ActionRef<'_>>> for Context {
-    fn from(value: Vec<ActionRef<'_>>) -> Self {
+    /// Remembers the ID of the webhook delivery that triggered the run.
+    pub fn for_delivery<S: Into<String>>(self, delivery: S) -> Self {
         Self {
-            actions: value.iter().map(|action_ref| action_ref.into()).collect(),
+            delivery: Some(delivery.into()),
+            ..self
         }
     }
+
+    pub fn delivery(&self) -> Option<&str> {
+        self.delivery.as_deref()
+    }
+
+    /// Includes the output of the commands in the responses to the webhook and in the status of
+    /// the run, which are public.
+    pub fn sharing_output(self) -> Self {
+        Self {
+            share_output: true,
+            ..self
+        }
+    }
+
+    pub fn shares_output(&self) -> bool {
+        self.share_output
+    }
+
+    pub fn actions(&self) -> &[ContextAction] {
+        &self.actions
+    }
+
+    /// The parameters of the trigger, as environment variables.
+    pub fn environment(&self) -> impl Iterator<Item = (String, &str)> {
+        self.parameters
+            .iter()
+            .map(|(name, value)| (parameter_variable(name), value.as_str()))
+    }
 }
 
 impl From<&ActionRef<'_>> for ContextAction {
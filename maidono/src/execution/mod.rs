@@ -1,5 +1,7 @@
 mod context;
+mod report;
 mod runner;
 
 pub use context::Context;
+pub use report::{RunReport, RunStatus};
 pub use runner::run_actions;
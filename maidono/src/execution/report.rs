@@ -0,0 +1,171 @@
+use maidono_core::control::{ActionOutcome, CommandOutcome};
+use maidono_core::utils::ActionPath;
+use rocket::serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+    #[serde(rename = "timeout")]
+    TimedOut,
+    Cancelled,
+}
+
+impl RunStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+            RunStatus::Skipped => "skipped",
+            RunStatus::TimedOut => "timeout",
+            RunStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// The result of running all the actions of a context.
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RunReport {
+    status: RunStatus,
+    actions: Vec<ActionReport>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct ActionReport {
+    action: String,
+    status: RunStatus,
+    commands: Vec<CommandReport>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct CommandReport {
+    command: String,
+    exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr: Option<String>,
+}
+
+impl RunReport {
+    pub fn new() -> Self {
+        Self {
+            status: RunStatus::Succeeded,
+            actions: Vec::new(),
+        }
+    }
+
+    /// The report of a run whose result is not known, or not known yet.
+    pub fn unfinished(status: RunStatus) -> Self {
+        Self {
+            status,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn status(&self) -> RunStatus {
+        self.status
+    }
+
+    pub fn without_output(self) -> Self {
+        Self {
+            actions: self
+                .actions
+                .into_iter()
+                .map(|action| ActionReport {
+                    commands: action
+                        .commands
+                        .into_iter()
+                        .map(|command| CommandReport {
+                            stdout: None,
+                            stderr: None,
+                            ..command
+                        })
+                        .collect(),
+                    ..action
+                })
+                .collect(),
+            ..self
+        }
+    }
+
+    /// The status of each action and the exit code of each command, without the output.
+    pub fn outcomes(&self) -> Vec<ActionOutcome> {
+        self.actions
+            .iter()
+            .map(|action| ActionOutcome {
+                action: action.action.clone(),
+                status: action.status.name().to_owned(),
+                commands: action
+                    .commands
+                    .iter()
+                    .map(|command| CommandOutcome {
+                        command: command.command.clone(),
+                        exit_code: command.exit_code,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    pub fn push(&mut self, action: ActionReport) {
+        if action.status == RunStatus::Failed {
+            self.status = RunStatus::Failed;
+        }
+        self.actions.push(action);
+    }
+}
+
+impl ActionReport {
+    pub fn new(path: &ActionPath) -> Self {
+        Self {
+            action: path.to_string(),
+            status: RunStatus::Succeeded,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn skipped(path: &ActionPath) -> Self {
+        Self {
+            status: RunStatus::Skipped,
+            ..Self::new(path)
+        }
+    }
+
+    pub fn status(&self) -> RunStatus {
+        self.status
+    }
+
+    pub fn push(&mut self, command: CommandReport) {
+        if command.exit_code != Some(0) {
+            self.status = RunStatus::Failed;
+        }
+        self.commands.push(command);
+    }
+}
+
+impl CommandReport {
+    pub fn new(command: &str, exit_code: Option<i32>) -> Self {
+        Self {
+            command: command.to_owned(),
+            exit_code,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    pub fn with_output(self, stdout: String, stderr: String) -> Self {
+        Self {
+            stdout: Some(stdout),
+            stderr: Some(stderr),
+            ..self
+        }
+    }
+}
@@ -1,71 +1,284 @@
 use crate::execution::context::ContextAction;
+use crate::execution::report::{ActionReport, CommandReport, RunReport, RunStatus};
 use crate::execution::Context;
 use crate::logger::Logger;
-use std::cmp::PartialEq;
-use std::path::PathBuf;
+use crate::state::RunLog;
+use maidono_core::control::{OutputStream, RunEvent};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 
-#[derive(PartialEq)]
-enum ActionRunStatus {
-    Running,
-    Failed,
-}
+/// How much of the output of each command is kept in the report of its run (in bytes).
+const KEPT_OUTPUT: usize = 64 * 1024;
 
-pub async fn run_actions(context: Context, logger: Logger) {
+pub async fn run_actions(context: Context, logger: Logger, log: RunLog) -> RunReport {
     let working_dir = std::env::current_dir().unwrap_or(PathBuf::from("."));
-    let mut status = ActionRunStatus::Running;
+    let mut report = RunReport::new();
     for action in context.actions() {
-        match status {
-            ActionRunStatus::Running => {
+        match report.status() {
+            RunStatus::Failed => {
+                logger
+                    .for_action(action.path())
+                    .log(format!("Skipping action '{}'", action.path()));
+                log.record(RunEvent::ActionSkipped {
+                    action: action.path().to_string(),
+                });
+                report.push(ActionReport::skipped(action.path()));
+            }
+            _ => {
+                let logger = logger.for_action(action.path());
                 logger.log(format!("Running action '{}'", action.path()));
-                status = run_single_action(logger, action, &working_dir).await;
-                match status {
-                    ActionRunStatus::Running => logger.log("  OK"),
-                    ActionRunStatus::Failed => logger.error_message(format!(
+                log.record(RunEvent::ActionStarted {
+                    action: action.path().to_string(),
+                });
+                let action_report =
+                    run_single_action(&logger, &log, &context, action, &working_dir).await;
+                match action_report.status() {
+                    RunStatus::Failed => logger.error_message(format!(
                         "  Failed to run action '{}' due to the error above.",
                         action.path()
                     )),
+                    _ => logger.log("  OK"),
                 }
-            }
-            ActionRunStatus::Failed => {
-                logger.log(format!("Skipping action '{}'", action.path()));
+                report.push(action_report);
             }
         }
     }
+    report
 }
 
 async fn run_single_action(
-    logger: Logger,
+    logger: &Logger,
+    log: &RunLog,
+    context: &Context,
     action: &ContextAction,
-    working_dir: &PathBuf,
-) -> ActionRunStatus {
-    let mut status = ActionRunStatus::Running;
+    working_dir: &Path,
+) -> ActionReport {
+    let mut report = ActionReport::new(action.path());
     for command in action.commands() {
+        let logger = logger.for_command(command);
         logger.log(format!("  bash:{}$ {}", working_dir.display(), command));
-        let command_result = tokio::process::Command::new("/bin/bash")
+        log.record(RunEvent::CommandStarted {
+            command: command.clone(),
+        });
+        let mut process = tokio::process::Command::new("/bin/bash");
+        process
             .arg("-c")
             .arg(command)
-            .status()
-            .await;
+            .envs(context.environment())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // the command gets its own process group, so that it can be killed with the processes
+            // it started when the run is cancelled
+            .process_group(0)
+            .kill_on_drop(true);
+
+        let command_result = match process.spawn() {
+            Ok(mut child) => {
+                let mut process_group = ProcessGroupGuard(child.id());
+                // the output is written to the logs as it comes, and kept in the report
+                let (stdout, stderr) = tokio::join!(
+                    forward_output(&logger, log, child.stdout.take(), OutputStream::Stdout),
+                    forward_output(&logger, log, child.stderr.take(), OutputStream::Stderr)
+                );
+                let status = child.wait().await;
+                // the processes left running by a command that exited are not killed
+                process_group.disarm();
+                status.map(|status| {
+                    let command_report =
+                        CommandReport::new(command, status.code()).with_output(stdout, stderr);
+                    (status, command_report)
+                })
+            }
+            Err(err) => Err(err),
+        };
 
         match command_result {
-            Ok(command_status) => {
+            Ok((command_status, command_report)) => {
                 if !command_status.success() {
                     logger.error_message(format!(
                         "    Command failed with exit status: {}",
                         command_status
                     ));
-                    status = ActionRunStatus::Failed;
                 }
+                log.record(RunEvent::CommandExited {
+                    exit_code: command_status.code(),
+                });
+                report.push(command_report);
             }
             Err(err) => {
                 logger.error_message(format!("    Could not run command: {}", err));
-                status = ActionRunStatus::Failed;
+                log.record(RunEvent::Output {
+                    stream: OutputStream::Stderr,
+                    line: format!("Could not run command: {}", err),
+                });
+                log.record(RunEvent::CommandExited { exit_code: None });
+                report.push(CommandReport::new(command, None));
             }
         }
 
-        if status == ActionRunStatus::Failed {
+        if report.status() == RunStatus::Failed {
+            break;
+        }
+    }
+    report
+}
+
+/// Kills the process group of a command when dropped before the command exited, which happens
+/// when its run is cancelled.
+struct ProcessGroupGuard(Option<u32>);
+
+impl ProcessGroupGuard {
+    fn disarm(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.0.and_then(|pid| libc::pid_t::try_from(pid).ok()) {
+            // SAFETY: kill only sends a signal, a negative PID targets the whole group
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// The longest line of output that is forwarded at once (in bytes), longer lines are split.
+const MAX_LINE: usize = 4 * 1024;
+
+/// Logs the lines written by a command, and returns the beginning of its output.
+async fn forward_output<R: AsyncRead + Unpin>(
+    logger: &Logger,
+    log: &RunLog,
+    output: Option<R>,
+    stream: OutputStream,
+) -> String {
+    let mut kept = String::new();
+    let mut truncated = false;
+    let Some(output) = output else {
+        return kept;
+    };
+
+    let mut reader = BufReader::new(output);
+    let mut line = Vec::new();
+    // the output is read in chunks, so that a command that never writes a newline doesn't fill
+    // the memory
+    while let Ok(available) = reader.fill_buf().await {
+        if available.is_empty() {
+            if !line.is_empty() {
+                forward_line(logger, log, &mut kept, &mut truncated, &line, stream);
+            }
             break;
         }
+        let room = MAX_LINE - line.len();
+        let (taken, complete) = match available.iter().take(room).position(|&byte| byte == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (available.len().min(room), available.len() >= room),
+        };
+        line.extend_from_slice(&available[..taken]);
+        reader.consume(taken);
+        if complete {
+            forward_line(logger, log, &mut kept, &mut truncated, &line, stream);
+            line.clear();
+        }
+    }
+    kept
+}
+
+fn forward_line(
+    logger: &Logger,
+    log: &RunLog,
+    kept: &mut String,
+    truncated: &mut bool,
+    line: &[u8],
+    stream: OutputStream,
+) {
+    logger.command_output(line, stream);
+    if *truncated {
+        return;
+    }
+
+    let text = String::from_utf8_lossy(line);
+    if kept.len() + text.len() > KEPT_OUTPUT {
+        // the log of the run stops at the same line as the report
+        *truncated = true;
+        log.record(RunEvent::OutputTruncated);
+        return;
+    }
+    log.record(RunEvent::Output {
+        stream,
+        line: text.trim_end_matches(['\n', '\r']).to_owned(),
+    });
+    kept.push_str(&text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::LogFormat;
+    use crate::state::Runs;
+    use maidono_core::actions::TriggerParameters;
+    use maidono_core::utils::ActionPath;
+    use rocket::config::LogLevel;
+
+    #[tokio::test]
+    async fn forward_output_splits_long_lines() {
+        let runs = Runs::default();
+        let context = Context::new(Vec::new(), TriggerParameters::new());
+        let record = runs.start(&ActionPath::from_parts("g", "a"), &context);
+        let logger = Logger::new(LogLevel::Off, LogFormat::Json);
+        let output = [
+            b"short\n".as_slice(),
+            &[b'a'; MAX_LINE + 10],
+            b"\nno newline",
+        ]
+        .concat();
+
+        let kept = forward_output(
+            &logger,
+            &runs.log_of(record.id()),
+            Some(output.as_slice()),
+            OutputStream::Stdout,
+        )
+        .await;
+
+        assert_eq!(kept.as_bytes(), output.as_slice());
+        let (_, entries) = runs.log(record.id(), 0).unwrap();
+        let lines: Vec<usize> = entries
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                RunEvent::Output { line, .. } => Some(line.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines, [5, MAX_LINE, 10, 10]);
+    }
+
+    #[tokio::test]
+    async fn forward_output_is_capped() {
+        let runs = Runs::default();
+        let context = Context::new(Vec::new(), TriggerParameters::new());
+        let record = runs.start(&ActionPath::from_parts("g", "a"), &context);
+        let logger = Logger::new(LogLevel::Off, LogFormat::Json);
+        let line = format!("{}\n", "a".repeat(99));
+        let output = line.repeat(KEPT_OUTPUT / 100 + 10);
+
+        let kept = forward_output(
+            &logger,
+            &runs.log_of(record.id()),
+            Some(output.as_bytes()),
+            OutputStream::Stdout,
+        )
+        .await;
+
+        assert_eq!(kept.len(), KEPT_OUTPUT / 100 * 100);
+        let (_, entries) = runs.log(record.id(), 0).unwrap();
+        assert_eq!(entries.len(), KEPT_OUTPUT / 100 + 1);
+        assert!(matches!(
+            entries.last().unwrap().event,
+            RunEvent::OutputTruncated
+        ));
     }
-    status
 }
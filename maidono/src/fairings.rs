@@ -1,5 +1,5 @@
-use crate::logger::Logger;
-use crate::state::load_initial_actions;
+use crate::logger::{log_format, Logger};
+use crate::state::{load_actions, LoadedActions, Metrics};
 use rocket::config::LogLevel;
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::{async_trait, Build, Rocket};
@@ -20,13 +20,16 @@ impl Fairing for InitialActionsLoader {
             .figment()
             .extract_inner("log_level")
             .unwrap_or(LogLevel::Critical);
-        let logger = Logger::from(log_level);
+        let logger = Logger::new(log_level, log_format(rocket.figment()));
 
-        match load_initial_actions() {
+        // the metrics are managed before the fairings are attached
+        let metrics = rocket.state::<Metrics>().cloned().unwrap_or_default();
+
+        match load_actions(&metrics, &logger) {
             Ok(actions) => {
                 logger.log("Successfully loaded actions");
                 logger.debug(&actions);
-                Ok(rocket.manage(actions))
+                Ok(rocket.manage(LoadedActions::new(actions)))
             }
             Err(error) => {
                 logger.error(error);
@@ -1,82 +1,311 @@
-use crate::execution::run_actions;
-use crate::hosts::{display_event_info, extract_signature, host_information_checks_out};
+use crate::execution::{run_actions, Context, RunReport, RunStatus};
+use crate::hosts::{display_event_info, Headers};
 use crate::logger::Logger;
-use crate::state::{ActionRef, Actions};
+use crate::state::{ActionRef, Actions, LoadedActions, Metrics, RejectionReason, RunRecord, Runs};
+use maidono_core::actions::{self, ResponseMode, TriggerParameters, WaitOptions};
 use maidono_core::problem;
-use maidono_core::utils::Result;
+use maidono_core::security::{check_host_headers, delivery_id, extract_signature};
+use maidono_core::utils::{ActionPath, Result};
 use rocket::data::ToByteUnit;
 use rocket::http::{Method, Status};
 use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest};
 use rocket::route::{Handler, Outcome as RouteOutcome};
-use rocket::{Data, Request, Response, Route, State};
-use std::path::PathBuf;
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::{Data, Request, Route, State};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
 
 #[derive(Clone)]
 pub struct WebhookHandler();
 
 impl WebhookHandler {
     pub(crate) fn routes() -> Vec<Route> {
-        vec![Route::new(Method::Post, "/<path..>", Self())]
+        [
+            Method::Get,
+            Method::Post,
+            Method::Put,
+            Method::Patch,
+            Method::Delete,
+        ]
+        .into_iter()
+        .map(|method| Route::ranked(0, method, "/<path..>", Self()))
+        .collect()
     }
 
     pub(crate) async fn handle_webhook<'r>(
         &self,
-        logger: Logger,
-        actions: &'r Actions,
+        logger: &Logger,
+        state: ServerState<'_>,
         request: &'r Request<'_>,
         data: Data<'r>,
     ) -> Result<RouteOutcome<'r>> {
-        let path = request
-            .segments::<PathBuf>(0..)
-            .map_err(|_| problem!("Could not get path of request"))?;
-
-        match path
-            .to_str()
-            .and_then(|path_str| actions.lookup_by_trigger(path_str))
-        {
-            None => Ok(RouteOutcome::Forward((data, Status::NotFound))),
-            Some(action_ref) => {
-                Self::handle_action(logger, actions, action_ref, request, data).await
+        let method = match trigger_method(request.method()) {
+            Some(method) => method,
+            None => return Ok(RouteOutcome::Forward((data, Status::NotFound))),
+        };
+
+        let segments = request.uri().path().segments();
+        let matched = state.actions.lookup_by_trigger(method, segments.clone());
+        if matched.is_empty() {
+            if state.actions.matches_disabled_action(method, segments) {
+                state.metrics.delivery_rejected(RejectionReason::Disabled);
+            } else if method != actions::Method::Get {
+                // other GET requests are for the web app
+                state.metrics.delivery_rejected(RejectionReason::NotFound);
+            }
+            return Ok(RouteOutcome::Forward((data, Status::NotFound)));
+        }
+
+        let body_size_limit = request.limits().get("bytes").unwrap_or(1.kibibytes());
+        let body = data
+            .open(body_size_limit)
+            .into_bytes()
+            .await
+            .map_err(|error| problem!("Could not read the body of the request").because(error))?;
+
+        let received_at = Instant::now();
+        let mut started_runs = Vec::new();
+        let mut rejection = None;
+        let mut failed = false;
+        for (action_ref, parameters) in matched {
+            let path = action_ref.path.as_ref().clone();
+            // the other actions are still handled if one of them fails
+            match Self::handle_action(logger, state, action_ref, parameters, request, &body) {
+                Ok(HandledAction::Started(run)) => started_runs.push(run),
+                Ok(HandledAction::Rejected(reason)) => rejection = rejection.max(Some(reason)),
+                Err(error) => {
+                    logger.for_action(&path).error(error);
+                    failed = true;
+                }
             }
         }
+
+        if started_runs.is_empty() {
+            if failed {
+                return Ok(RouteOutcome::Error(Status::InternalServerError));
+            }
+            // the delivery is counted once, with the reason of the action that went the furthest
+            if let Some(reason) = rejection {
+                state.metrics.delivery_rejected(reason);
+            }
+            return Ok(RouteOutcome::Error(Status::BadRequest));
+        }
+
+        let base_url = BaseUrl::of(request);
+        let mut status = Status::Ok;
+        let mut responses = Vec::new();
+        for run in started_runs {
+            let record = match run.wait {
+                None => run.record,
+                Some(options) => {
+                    let deadline = received_at + Duration::from_secs(options.timeout());
+                    let record = match tokio::time::timeout_at(deadline, run.handle).await {
+                        Ok(Ok(record)) => record,
+                        Ok(Err(join_error)) if join_error.is_cancelled() => run
+                            .record
+                            .with_report(RunReport::unfinished(RunStatus::Cancelled)),
+                        Ok(Err(join_error)) => {
+                            logger.error_message(format!(
+                                "Run {} was interrupted: {}",
+                                run.record.id(),
+                                join_error
+                            ));
+                            run.record
+                                .with_report(RunReport::unfinished(RunStatus::Failed))
+                        }
+                        Err(_) => {
+                            let logger = logger
+                                .for_action(run.record.action())
+                                .for_run(run.record.id());
+                            logger.warning_message(format!(
+                                "Run {} did not finish within {} seconds",
+                                run.record.id(),
+                                options.timeout()
+                            ));
+                            run.record
+                                .with_report(RunReport::unfinished(RunStatus::TimedOut))
+                        }
+                    };
+                    if record.status() != RunStatus::Succeeded && status == Status::Ok {
+                        status = Status::from_code(options.error_status())
+                            .unwrap_or(Status::InternalServerError);
+                    }
+                    record
+                }
+            };
+            responses.push(RunResponse::new(&base_url, record.public()));
+        }
+
+        Ok(RouteOutcome::from(
+            request,
+            (status, Json(WebhookResponse { runs: responses })),
+        ))
     }
 
-    async fn handle_action<'a>(
-        logger: Logger,
-        actions: &'a Actions,
-        action_ref: ActionRef<'a>,
-        request: &'a Request<'_>,
-        data: Data<'a>,
-    ) -> Result<RouteOutcome<'a>> {
+    /// Checks the request against one of the matching actions, and runs it if it is accepted.
+    fn handle_action(
+        logger: &Logger,
+        state: ServerState<'_>,
+        action_ref: ActionRef<'_>,
+        parameters: TriggerParameters,
+        request: &Request<'_>,
+        body: &[u8],
+    ) -> Result<HandledAction> {
         let host_ref = action_ref.action.origin();
-        if !host_information_checks_out(host_ref, request) {
-            logger.debug_message("Webhook trigger blocked because of invalid or missing headers");
-            return Ok(RouteOutcome::Error(Status::BadRequest));
+        let headers = Headers(request.headers());
+        let mut logger = logger.for_action(&action_ref.path);
+        if let Some(delivery_id) = delivery_id(host_ref, &headers) {
+            logger = logger.for_delivery(delivery_id);
+        }
+        let path = action_ref.path.as_ref().clone();
+        state
+            .metrics
+            .delivery_received(&path.to_string(), &host_ref.to_string());
+
+        if check_host_headers(host_ref, &headers).is_err() {
+            logger.debug_message(format!(
+                "Trigger of action '{}' blocked because of invalid or missing headers",
+                action_ref.path
+            ));
+            return Ok(HandledAction::Rejected(RejectionReason::BadHeaders));
         }
-        let body_size_limit = request.limits().get("bytes").unwrap_or(1.kibibytes());
         if let Some(secret) = action_ref.action.secret() {
-            let signature_is_valid = if let Some(signature) = extract_signature(host_ref, request) {
-                signature.matches(secret, data.open(body_size_limit)).await
-            } else {
-                false
+            let signature_is_valid = match extract_signature(host_ref, &headers) {
+                Some(signature) => signature.matches(secret, body),
+                None => false,
             };
 
             if !signature_is_valid {
-                logger.debug_message(
-                    "Webhook trigger blocked because of invalid or missing signature",
-                );
-                return Ok(RouteOutcome::Error(Status::BadRequest));
+                logger.debug_message(format!(
+                    "Trigger of action '{}' blocked because of invalid or missing signature",
+                    action_ref.path
+                ));
+                return Ok(HandledAction::Rejected(RejectionReason::BadSignature));
             }
         }
 
         logger.log(format!("Action '{}' triggered by webhook", action_ref.path));
-        display_event_info(&logger, host_ref, request);
+        display_event_info(&logger, host_ref, &headers);
 
-        let ctx = actions.load_context_for(action_ref)?;
-        tokio::spawn(run_actions(ctx, logger));
-        Ok(RouteOutcome::Success(
-            Response::build().status(Status::Ok).finalize(),
-        ))
+        let wait = match action_ref.action.response_mode() {
+            ResponseMode::Immediate => None,
+            ResponseMode::Wait(options) => Some(options.clone()),
+        };
+        let mut ctx = state.actions.load_context_for(action_ref, parameters)?;
+        if let Some(delivery_id) = delivery_id(host_ref, &headers) {
+            ctx = ctx.for_delivery(delivery_id);
+        }
+        if wait.as_ref().is_some_and(WaitOptions::includes_output) {
+            ctx = ctx.sharing_output();
+        }
+
+        let (record, handle) = start_run(&logger, state, &path, ctx);
+        Ok(HandledAction::Started(StartedRun {
+            record,
+            wait,
+            handle,
+        }))
+    }
+}
+
+/// Runs the actions of a context in the background, and keeps track of the run.
+pub(crate) fn start_run(
+    logger: &Logger,
+    state: ServerState<'_>,
+    path: &ActionPath,
+    ctx: Context,
+) -> (RunRecord, JoinHandle<RunRecord>) {
+    let record = state.runs.start(path, &ctx);
+    let logger = logger.for_run(record.id());
+    logger.debug_message(format!("Started run {} of action '{}'", record.id(), path));
+    state.metrics.run_started(record.action());
+
+    let runs = state.runs.clone();
+    let metrics = state.metrics.clone();
+    let started = record.clone();
+    let log = state.runs.log_of(record.id());
+    let handle = tokio::spawn(async move {
+        let report = run_actions(ctx, logger, log).await;
+        runs.finish(started, report, &metrics)
+    });
+    state.runs.attach(record.id(), handle.abort_handle());
+
+    (record, handle)
+}
+
+/// The managed state used to handle webhooks.
+#[derive(Clone, Copy)]
+pub(crate) struct ServerState<'a> {
+    pub actions: &'a Actions,
+    pub runs: &'a Runs,
+    pub metrics: &'a Metrics,
+}
+
+enum HandledAction {
+    Started(StartedRun),
+    Rejected(RejectionReason),
+}
+
+struct StartedRun {
+    record: RunRecord,
+    wait: Option<WaitOptions>,
+    handle: JoinHandle<RunRecord>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct WebhookResponse {
+    runs: Vec<RunResponse>,
+}
+
+/// A run, with the URL where its status can be polled.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct RunResponse {
+    #[serde(flatten)]
+    record: RunRecord,
+    status_url: String,
+}
+
+impl RunResponse {
+    pub(crate) fn new(base_url: &BaseUrl, record: RunRecord) -> Self {
+        Self {
+            status_url: format!("{}/runs/{}", base_url.0, record.id()),
+            record,
+        }
+    }
+}
+
+/// The scheme and host of the server, if the request tells the host. The URLs built with it are
+/// relative otherwise.
+pub(crate) struct BaseUrl(String);
+
+impl BaseUrl {
+    fn of(request: &Request<'_>) -> Self {
+        match request.host() {
+            Some(host) => {
+                let scheme = if request.rocket().config().tls_enabled() {
+                    "https"
+                } else {
+                    "http"
+                };
+                BaseUrl(format!("{}://{}", scheme, host))
+            }
+            None => BaseUrl(String::new()),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for BaseUrl {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(BaseUrl::of(req))
     }
 }
 
@@ -84,10 +313,27 @@ impl WebhookHandler {
 impl Handler for WebhookHandler {
     async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> RouteOutcome<'r> {
         let logger = req.guard::<Logger>().await.unwrap();
-        match req.guard::<&State<Actions>>().await {
-            Outcome::Success(actions) => {
+        let (runs, metrics) = match (
+            req.guard::<&State<Runs>>().await,
+            req.guard::<&State<Metrics>>().await,
+        ) {
+            (Outcome::Success(runs), Outcome::Success(metrics)) => (runs, metrics),
+            _ => return RouteOutcome::Error(Status::InternalServerError),
+        };
+        match req.guard::<&State<LoadedActions>>().await {
+            Outcome::Success(loaded) => {
+                let actions = loaded.get();
                 match self
-                    .handle_webhook(logger, actions.inner(), req, data)
+                    .handle_webhook(
+                        &logger,
+                        ServerState {
+                            actions: &actions,
+                            runs: runs.inner(),
+                            metrics: metrics.inner(),
+                        },
+                        req,
+                        data,
+                    )
                     .await
                 {
                     Ok(outcome) => outcome,
@@ -102,3 +348,14 @@ impl Handler for WebhookHandler {
         }
     }
 }
+
+fn trigger_method(method: Method) -> Option<actions::Method> {
+    match method {
+        Method::Get => Some(actions::Method::Get),
+        Method::Post => Some(actions::Method::Post),
+        Method::Put => Some(actions::Method::Put),
+        Method::Patch => Some(actions::Method::Patch),
+        Method::Delete => Some(actions::Method::Delete),
+        _ => None,
+    }
+}
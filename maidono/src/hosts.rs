@@ -1,58 +1,24 @@
 use crate::logger::Logger;
-use crate::security::Signature;
 use maidono_core::actions::HostRef;
-use rocket::Request;
+use maidono_core::security::{delivery_id, event_type, RequestHeaders};
+use rocket::http::HeaderMap;
 
-pub fn host_information_checks_out(host_ref: &HostRef, request: &Request) -> bool {
-    match host_ref {
-        HostRef::GitHub => {
-            request
-                .headers()
-                .get_one("User-Agent")
-                .map(|ua| ua.starts_with("GitHub-Hookshot"))
-                .unwrap_or(false)
-                && request.headers().contains("X-Github-Delivery")
-                && request.headers().contains("X-Github-Event")
-        }
-        _ => true,
-    }
-}
-
-pub fn display_event_info(logger: &Logger, host_ref: &HostRef, request: &Request) {
-    match host_ref {
-        HostRef::GitHub => {
-            if let Some(delivery_id) = request.headers().get_one("X-Github-Delivery") {
-                logger.log(format!("  Github delivery ID: {}", delivery_id));
-            }
-            if let Some(delivery_id) = request.headers().get_one("X-Github-Event") {
-                logger.log(format!("  Github event type: {}", delivery_id));
-            }
-        }
-        _ => {}
-    }
-}
+/// The headers of a Rocket request, for the checks of `maidono_core::security`.
+pub struct Headers<'r>(pub &'r HeaderMap<'r>);
 
-pub fn extract_signature<'a>(host_ref: &HostRef, request: &'a Request) -> Option<Signature> {
-    match host_ref {
-        HostRef::GitHub => request
-            .headers()
-            .get_one("X-Hub-Signature-256")
-            .and_then(decode_hub256_signature),
-        _ => None,
+impl RequestHeaders for Headers<'_> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.0.get_one(name)
     }
 }
 
-fn decode_hub256_signature(signature: &str) -> Option<Signature> {
-    // 7 chars for the prefix + 64 chars for the hash
-    if signature.starts_with("sha256=") && signature.len() == 71 {
-        let mut signature_bytes = [0u8; 32];
-        let decoded = hex::decode_to_slice(&signature[7..], &mut signature_bytes).is_ok();
-        if decoded {
-            Some(Signature::HS256Hex(signature_bytes))
-        } else {
-            None
+pub fn display_event_info(logger: &Logger, host_ref: &HostRef, headers: &Headers) {
+    if host_ref == &HostRef::GitHub {
+        if let Some(delivery_id) = delivery_id(host_ref, headers) {
+            logger.log(format!("  Github delivery ID: {}", delivery_id));
+        }
+        if let Some(event_type) = event_type(host_ref, headers) {
+            logger.log(format!("  Github event type: {}", event_type));
         }
-    } else {
-        None
     }
 }
@@ -1,9 +1,15 @@
+use chrono::{SecondsFormat, Utc};
+use maidono_core::control::OutputStream;
 use maidono_core::utils::Error;
 use rocket::config::LogLevel;
+use rocket::figment::Figment;
 use rocket::request::{FromRequest, Outcome};
-use rocket::{Config, Request};
+use rocket::serde::json::to_string;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::Request;
 use std::convert::Infallible;
 use std::fmt::{Debug, Display};
+use std::io::Write;
 
 pub const LOG_LEVEL_HIGHEST: u8 = 4;
 pub const LOG_LEVEL_ERROR: u8 = 3;
@@ -11,9 +17,47 @@ pub const LOG_LEVEL_WARNING: u8 = 2;
 pub const LOG_LEVEL_INFO: u8 = 1;
 pub const LOG_LEVEL_DEBUG: u8 = 0;
 
-#[derive(Clone, Copy)]
+/// How the lines of the logs are written, set with the `log_format` key of the configuration.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines, prefixed with a timestamp.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// What a log line is about. These fields are only written in the JSON format, where the text
+/// of the messages is not meant to be parsed.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(crate = "rocket::serde")]
+struct LogFields {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct JsonLine<'a> {
+    timestamp: String,
+    level: &'a str,
+    message: &'a str,
+    #[serde(flatten)]
+    fields: &'a LogFields,
+}
+
+#[derive(Clone)]
 pub struct Logger {
     minimum_level: u8,
+    format: LogFormat,
+    fields: LogFields,
 }
 
 #[rocket::async_trait]
@@ -21,14 +65,27 @@ impl<'r> FromRequest<'r> for Logger {
     type Error = Infallible;
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        req.guard::<&Config>()
-            .await
-            .map(|config| config.log_level.into())
+        let rocket = req.rocket();
+        Outcome::Success(Self::new(
+            rocket.config().log_level,
+            log_format(rocket.figment()),
+        ))
     }
 }
 
 impl From<LogLevel> for Logger {
     fn from(log_level: LogLevel) -> Self {
+        Self::new(log_level, LogFormat::default())
+    }
+}
+
+/// Reads the log format from the configuration, falling back to text.
+pub fn log_format(figment: &Figment) -> LogFormat {
+    figment.extract_inner("log_format").unwrap_or_default()
+}
+
+impl Logger {
+    pub fn new(log_level: LogLevel, format: LogFormat) -> Self {
         Self {
             minimum_level: match log_level {
                 LogLevel::Critical => LOG_LEVEL_WARNING,
@@ -36,44 +93,110 @@ impl From<LogLevel> for Logger {
                 LogLevel::Debug => LOG_LEVEL_DEBUG,
                 LogLevel::Off => LOG_LEVEL_HIGHEST,
             },
+            format,
+            fields: LogFields::default(),
         }
     }
-}
 
-impl Logger {
+    pub fn for_action<D: Display>(&self, action: D) -> Self {
+        let mut logger = self.clone();
+        logger.fields.action = Some(action.to_string());
+        logger
+    }
+
+    pub fn for_run<D: Display>(&self, run: D) -> Self {
+        let mut logger = self.clone();
+        logger.fields.run = Some(run.to_string());
+        logger
+    }
+
+    pub fn for_delivery<D: Display>(&self, delivery: D) -> Self {
+        let mut logger = self.clone();
+        logger.fields.delivery = Some(delivery.to_string());
+        logger
+    }
+
+    pub fn for_command<D: Display>(&self, command: D) -> Self {
+        let mut logger = self.clone();
+        logger.fields.command = Some(command.to_string());
+        logger
+    }
+
     pub fn error(&self, error: Error) {
         if self.minimum_level <= LOG_LEVEL_ERROR {
-            println!("[maidono: error] {:?}", error)
+            self.write("error", format!("{:?}", error))
         }
     }
 
     pub fn error_message<D: Display>(&self, message: D) {
         if self.minimum_level <= LOG_LEVEL_ERROR {
-            println!("[maidono: error] {}", message)
+            self.write("error", message)
         }
     }
 
-    // pub fn warning_message<D: Display>(&self, message: D) {
-    //     if self.minimum_level <= LOG_LEVEL_WARNING {
-    //         println!("[maidono: warning] {}", message)
-    //     }
-    // }
+    pub fn warning_message<D: Display>(&self, message: D) {
+        if self.minimum_level <= LOG_LEVEL_WARNING {
+            self.write("warning", message)
+        }
+    }
 
     pub fn log<D: Display>(&self, message: D) {
         if self.minimum_level <= LOG_LEVEL_INFO {
-            println!("[maidono: info] {}", message)
+            self.write("info", message)
         }
     }
 
     pub fn debug<D: Debug>(&self, message: D) {
-        if self.minimum_level <= LOG_LEVEL_DEBUG {
-            println!("[maidono: debug] {:#?}", message)
+        if self.minimum_level == LOG_LEVEL_DEBUG {
+            self.write("debug", format!("{:#?}", message))
         }
     }
 
     pub fn debug_message<D: Display>(&self, message: D) {
-        if self.minimum_level <= LOG_LEVEL_DEBUG {
-            println!("[maidono: debug] {}", message)
+        if self.minimum_level == LOG_LEVEL_DEBUG {
+            self.write("debug", message)
+        }
+    }
+
+    /// Writes a line of output of a command. It is always written whatever the log level, as is
+    /// in the text format, and as an info (stdout) or warning (stderr) line in the JSON format.
+    pub fn command_output(&self, line: &[u8], stream: OutputStream) {
+        match self.format {
+            LogFormat::Text => {
+                let _ = match stream {
+                    OutputStream::Stdout => std::io::stdout().write_all(line),
+                    OutputStream::Stderr => std::io::stderr().write_all(line),
+                };
+            }
+            LogFormat::Json => {
+                let line = String::from_utf8_lossy(line);
+                let line = line.trim_end_matches(['\n', '\r']);
+                match stream {
+                    OutputStream::Stdout => self.write("info", line),
+                    OutputStream::Stderr => self.write("warning", line),
+                }
+            }
+        }
+    }
+
+    fn write<D: Display>(&self, level: &str, message: D) {
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        match self.format {
+            LogFormat::Text => println!("{} [maidono: {}] {}", timestamp, level, message),
+            LogFormat::Json => {
+                let message = message.to_string();
+                let line = JsonLine {
+                    timestamp,
+                    level,
+                    // the indentation only makes sense in the text format
+                    message: message.trim_start(),
+                    fields: &self.fields,
+                };
+                match to_string(&line) {
+                    Ok(json) => println!("{}", json),
+                    Err(error) => println!("[maidono: error] Could not write log line: {}", error),
+                }
+            }
         }
     }
 }
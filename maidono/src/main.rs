@@ -1,21 +1,27 @@
+mod access;
+mod api;
+mod control;
 mod execution;
 mod fairings;
 mod handler;
 mod hosts;
 mod logger;
 mod payload;
-mod security;
 mod state;
+mod status;
 mod version;
 
+use crate::control::ControlSocket;
 use crate::fairings::InitialActionsLoader;
 use crate::handler::WebhookHandler;
+use crate::state::{Metrics, Runs};
 use maidono_core::problem;
 use maidono_core::utils::path::{SERVER_CONFIG_FILE, WEB_APP_ASSETS, WEB_APP_INDEX};
 use maidono_core::utils::Error;
 use rocket::figment::providers::{Format, Serialized, Toml};
 use rocket::figment::{Figment, Profile};
 use rocket::fs::{FileServer, Options};
+use rocket::{Build, Rocket};
 
 #[rocket::main]
 async fn main() -> Result<(), Error> {
@@ -37,19 +43,40 @@ async fn main() -> Result<(), Error> {
             ),
         ));
 
-    if let Err(error) = rocket::custom(figment)
+    let metrics = Metrics::default();
+    let metrics_port = figment.extract_inner::<u16>("metrics_port").ok();
+
+    let mut server = rocket::custom(figment.clone())
         .mount(
             "/",
             FileServer::new(WEB_APP_INDEX, Options::IndexFile).rank(1),
         )
         .mount("/assets", FileServer::from(WEB_APP_ASSETS).rank(-1))
         .mount("/", WebhookHandler::routes())
+        .mount("/", status::routes())
+        .mount("/api", api::routes())
+        .manage(Runs::default())
+        .manage(metrics.clone())
         .attach(InitialActionsLoader())
-        .launch()
-        .await
-    {
-        Err(problem!("Failed to launch Rocket server").because(error.pretty_print()))
-    } else {
-        Ok(())
+        .attach(ControlSocket());
+
+    match metrics_port {
+        None => {
+            server = server.mount("/", status::metrics_routes());
+            launch(server).await
+        }
+        Some(port) => {
+            let metrics_server = rocket::custom(figment.merge(("port", port)))
+                .mount("/", status::metrics_routes())
+                .manage(metrics);
+            tokio::try_join!(launch(server), launch(metrics_server)).map(|_| ())
+        }
+    }
+}
+
+async fn launch(rocket: Rocket<Build>) -> Result<(), Error> {
+    match rocket.launch().await {
+        Err(error) => Err(problem!("Failed to launch Rocket server").because(error.pretty_print())),
+        Ok(_) => Ok(()),
     }
 }
@@ -3,6 +3,7 @@ use rocket::data::{FromData, Outcome};
 use rocket::serde::json::{Error, Json, Value};
 use rocket::{async_trait, Data, Request};
 
+#[allow(dead_code)]
 pub struct JsonPayload {
     _value: Value,
 }
@@ -1,13 +1,18 @@
 use crate::execution::Context;
-use maidono_core::actions::{read_all_groups, Action};
+use crate::logger::Logger;
+use crate::state::Metrics;
+use chrono::{DateTime, Utc};
+use maidono_core::actions::{Action, ActionIndex, Method, TriggerParameters};
 use maidono_core::problem;
 use maidono_core::utils::{ActionPath, Result};
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 
 #[derive(Debug)]
 pub struct Actions {
-    by_path: BTreeMap<ActionPath, Action>,
+    index: ActionIndex,
+    loaded_at: DateTime<Utc>,
 }
 
 pub struct ActionRef<'a> {
@@ -16,63 +21,148 @@ pub struct ActionRef<'a> {
 }
 
 impl Actions {
-    pub(crate) fn lookup_by_trigger(&self, uri_path: &str) -> Option<ActionRef> {
-        for (path, action) in &self.by_path {
-            if action.trigger() == uri_path {
-                return Some(ActionRef {
+    pub fn new(index: ActionIndex) -> Self {
+        Self {
+            index,
+            loaded_at: Utc::now(),
+        }
+    }
+
+    pub(crate) fn lookup_by_trigger<'a, S>(
+        &self,
+        method: Method,
+        uri_path: S,
+    ) -> Vec<(ActionRef<'_>, TriggerParameters)>
+    where
+        S: IntoIterator<Item = &'a str>,
+    {
+        self.index
+            .lookup(method, uri_path)
+            .into_iter()
+            .map(|(path, action, parameters)| {
+                let action_ref = ActionRef {
                     path: Cow::Borrowed(path),
                     action,
-                });
-            }
-        }
-        None
+                };
+                (action_ref, parameters)
+            })
+            .collect()
     }
 
-    pub(crate) fn load_context_for<'a>(&'a self, action_ref: ActionRef<'a>) -> Result<Context> {
-        Ok(self.load_actions_from_ref(action_ref)?.into())
+    pub(crate) fn get(&self, path: &ActionPath) -> Option<ActionRef<'_>> {
+        let (path, action) = self.index.get(path)?;
+        Some(ActionRef {
+            path: Cow::Borrowed(path),
+            action,
+        })
     }
 
-    fn load_actions_from_path(&self, action_path: ActionPath) -> Result<Vec<ActionRef>> {
-        match self.by_path.get(&action_path) {
-            None => Err(problem!("action '{}' not found", action_path)),
-            Some(action_before) => Ok(self.load_actions_from_ref(ActionRef {
-                path: Cow::Owned(action_path),
-                action: action_before,
-            })?),
-        }
+    pub(crate) fn loaded_at(&self) -> DateTime<Utc> {
+        self.loaded_at
+    }
+
+    pub(crate) fn enabled_count(&self) -> usize {
+        self.index.enabled_count()
     }
 
-    fn load_actions_from_ref<'a>(
-        &'a self,
-        action_ref: ActionRef<'a>,
-    ) -> Result<Vec<ActionRef<'a>>> {
+    /// The enabled actions whose trigger uses one of the paths reserved by the server.
+    pub(crate) fn reserved_path_collisions(&self) -> Vec<(&ActionPath, &'static str)> {
+        self.index
+            .actions()
+            .filter(|(path, _)| self.index.is_enabled(path))
+            .filter_map(|(path, action)| Some((path, action.trigger().reserved_path()?)))
+            .collect()
+    }
+
+    /// Whether a request would have triggered an action if it was enabled.
+    pub(crate) fn matches_disabled_action<'a, S>(&self, method: Method, uri_path: S) -> bool
+    where
+        S: IntoIterator<Item = &'a str> + Clone,
+    {
+        !self.index.lookup_disabled(method, uri_path).is_empty()
+    }
+
+    pub(crate) fn load_context_for(
+        &self,
+        action_ref: ActionRef<'_>,
+        parameters: TriggerParameters,
+    ) -> Result<Context> {
+        let plan = self.index.plan(action_ref.path.as_ref()).ok_or(problem!(
+            "no execution plan for action '{}'",
+            action_ref.path
+        ))?;
+
         let mut actions = Vec::new();
-        for path_before in action_ref.action.before() {
-            let path = ActionPath::parse(path_before)?;
-            actions.append(&mut self.load_actions_from_path(path)?);
+        for path in plan.steps() {
+            let (path, action) = self
+                .index
+                .get(path)
+                .ok_or(problem!("action '{}' not found", path))?;
+            actions.push(ActionRef {
+                path: Cow::Borrowed(path),
+                action,
+            });
         }
-        let this_action_idx = actions.len();
-        for path_after in action_ref.action.after() {
-            let path = ActionPath::parse(path_after)?;
-            actions.append(&mut self.load_actions_from_path(path)?);
+        Ok(Context::new(actions, parameters))
+    }
+}
+
+/// The actions used by the server, which are replaced when they are reloaded.
+#[derive(Clone)]
+pub struct LoadedActions {
+    current: Arc<RwLock<Arc<Actions>>>,
+    last_load_failed: Arc<AtomicBool>,
+}
+
+impl LoadedActions {
+    pub fn new(actions: Actions) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(actions))),
+            last_load_failed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether the last load of the actions succeeded. When a reload fails, the previous actions are kept.
+    pub fn last_load_succeeded(&self) -> bool {
+        !self.last_load_failed.load(Ordering::Relaxed)
+    }
+
+    /// The actions as they are now. Requests keep using them even if they are reloaded meanwhile.
+    pub fn get(&self) -> Arc<Actions> {
+        match self.current.read() {
+            Ok(current) => current.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Reads the actions again, and replaces the current ones if they are valid.
+    pub fn reload(&self, metrics: &Metrics, logger: &Logger) -> Result<Arc<Actions>> {
+        let result = load_actions(metrics, logger);
+        self.last_load_failed
+            .store(result.is_err(), Ordering::Relaxed);
+
+        let actions = Arc::new(result?);
+        match self.current.write() {
+            Ok(mut current) => *current = actions.clone(),
+            Err(poisoned) => *poisoned.into_inner() = actions.clone(),
         }
-        actions.insert(this_action_idx, action_ref);
         Ok(actions)
     }
 }
 
-pub fn load_initial_actions() -> Result<Actions> {
-    let groups = read_all_groups()?;
-    let mut actions = Actions {
-        by_path: BTreeMap::new(),
-    };
+/// Reads the actions, counting the load in the metrics and warning about the actions that can't
+/// be triggered because of the routes of the server.
+pub fn load_actions(metrics: &Metrics, logger: &Logger) -> Result<Actions> {
+    let result = ActionIndex::load().map(Actions::new);
+    metrics.config_loaded(result.is_ok());
 
-    for (group_name, group) in groups {
-        for (action_name, action) in group.into_enumerated_actions() {
-            let path = ActionPath::from_parts(group_name.clone(), action_name);
-            actions.by_path.insert(path, action);
+    if let Ok(actions) = &result {
+        for (path, reserved) in actions.reserved_path_collisions() {
+            logger.warning_message(format!(
+                "The trigger of action '{}' uses the path '/{}', which is reserved by the server",
+                path, reserved
+            ));
         }
     }
-
-    Ok(actions)
+    result
 }
@@ -0,0 +1,384 @@
+use crate::execution::RunStatus;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// The upper bounds of the buckets of the run duration histograms, in seconds.
+const DURATION_BUCKETS: [f64; 10] = [0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0];
+
+/// Why a delivery didn't start any run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RejectionReason {
+    BadHeaders,
+    BadSignature,
+    NotFound,
+    Disabled,
+}
+
+/// Counters about the deliveries and the runs, exposed in the Prometheus text format.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    data: Arc<Mutex<MetricsData>>,
+}
+
+#[derive(Default)]
+struct MetricsData {
+    deliveries: BTreeMap<(String, String), u64>,
+    rejections: BTreeMap<RejectionReason, u64>,
+    runs_started: BTreeMap<String, u64>,
+    runs_succeeded: BTreeMap<String, u64>,
+    runs_failed: BTreeMap<String, u64>,
+    runs_cancelled: BTreeMap<String, u64>,
+    run_durations: BTreeMap<String, Histogram>,
+    runs_in_progress: u64,
+    config_loads: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Metrics {
+    pub fn delivery_received(&self, action: &str, origin: &str) {
+        *self
+            .lock()
+            .deliveries
+            .entry((action.to_owned(), origin.to_owned()))
+            .or_default() += 1;
+    }
+
+    pub fn delivery_rejected(&self, reason: RejectionReason) {
+        *self.lock().rejections.entry(reason).or_default() += 1;
+    }
+
+    pub fn run_started(&self, action: &str) {
+        let mut data = self.lock();
+        *data.runs_started.entry(action.to_owned()).or_default() += 1;
+        data.runs_in_progress += 1;
+    }
+
+    pub fn run_finished(&self, action: &str, status: RunStatus, duration: Duration) {
+        let mut data = self.lock();
+        let counter = match status {
+            RunStatus::Succeeded => &mut data.runs_succeeded,
+            RunStatus::Cancelled => &mut data.runs_cancelled,
+            _ => &mut data.runs_failed,
+        };
+        *counter.entry(action.to_owned()).or_default() += 1;
+        data.run_durations
+            .entry(action.to_owned())
+            .or_default()
+            .observe(duration.as_secs_f64());
+        data.runs_in_progress = data.runs_in_progress.saturating_sub(1);
+    }
+
+    pub fn config_loaded(&self, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        *self.lock().config_loads.entry(result).or_default() += 1;
+    }
+
+    pub fn runs_in_progress(&self) -> u64 {
+        self.lock().runs_in_progress
+    }
+
+    /// Renders all the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let data = self.lock();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "maidono_deliveries_received_total",
+            "counter",
+            "Deliveries received, by action and origin.",
+        );
+        for ((action, origin), count) in &data.deliveries {
+            sample(
+                &mut out,
+                "maidono_deliveries_received_total",
+                &[("action", action), ("origin", origin)],
+                *count,
+            );
+        }
+
+        header(
+            &mut out,
+            "maidono_deliveries_rejected_total",
+            "counter",
+            "Deliveries that did not start any run, by reason.",
+        );
+        for (reason, count) in &data.rejections {
+            sample(
+                &mut out,
+                "maidono_deliveries_rejected_total",
+                &[("reason", reason.as_str())],
+                *count,
+            );
+        }
+
+        for (name, help, counters) in [
+            (
+                "maidono_runs_started_total",
+                "Runs started, by triggering action.",
+                &data.runs_started,
+            ),
+            (
+                "maidono_runs_succeeded_total",
+                "Runs that succeeded, by triggering action.",
+                &data.runs_succeeded,
+            ),
+            (
+                "maidono_runs_failed_total",
+                "Runs that failed, by triggering action.",
+                &data.runs_failed,
+            ),
+            (
+                "maidono_runs_cancelled_total",
+                "Runs that were cancelled, by triggering action.",
+                &data.runs_cancelled,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            for (action, count) in counters {
+                sample(&mut out, name, &[("action", action)], *count);
+            }
+        }
+
+        header(
+            &mut out,
+            "maidono_run_duration_seconds",
+            "histogram",
+            "Duration of the finished runs, by triggering action.",
+        );
+        for (action, histogram) in &data.run_durations {
+            histogram.render(&mut out, "maidono_run_duration_seconds", action);
+        }
+
+        header(
+            &mut out,
+            "maidono_runs_in_progress",
+            "gauge",
+            "Runs that have started and not finished yet.",
+        );
+        sample(
+            &mut out,
+            "maidono_runs_in_progress",
+            &[],
+            data.runs_in_progress,
+        );
+
+        header(
+            &mut out,
+            "maidono_config_loads_total",
+            "counter",
+            "Loads of the actions config, by result.",
+        );
+        for (result, count) in &data.config_loads {
+            sample(
+                &mut out,
+                "maidono_config_loads_total",
+                &[("result", result)],
+                *count,
+            );
+        }
+
+        out
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MetricsData> {
+        // the counters are always left in a consistent state, even if a thread panicked
+        self.data
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl RejectionReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RejectionReason::BadHeaders => "bad_headers",
+            RejectionReason::BadSignature => "bad_signature",
+            RejectionReason::NotFound => "not_found",
+            RejectionReason::Disabled => "disabled",
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+
+    fn render(&self, out: &mut String, name: &str, action: &str) {
+        let bucket_name = format!("{}_bucket", name);
+        for (count, bound) in self.buckets.iter().zip(DURATION_BUCKETS) {
+            let bound = bound.to_string();
+            sample(
+                out,
+                &bucket_name,
+                &[("action", action), ("le", &bound)],
+                *count,
+            );
+        }
+        sample(
+            out,
+            &bucket_name,
+            &[("action", action), ("le", "+Inf")],
+            self.count,
+        );
+        let _ = writeln!(
+            out,
+            "{}_sum{} {}",
+            name,
+            labels(&[("action", action)]),
+            self.sum
+        );
+        sample(
+            out,
+            &format!("{}_count", name),
+            &[("action", action)],
+            self.count,
+        );
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, label_pairs: &[(&str, &str)], value: u64) {
+    let _ = writeln!(out, "{}{} {}", name, labels(label_pairs), value);
+}
+
+fn labels(label_pairs: &[(&str, &str)]) -> String {
+    if label_pairs.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = label_pairs
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label(value)))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines_of<'a>(rendered: &'a str, name: &str) -> Vec<&'a str> {
+        rendered
+            .lines()
+            .filter(|line| line.starts_with(name))
+            .collect()
+    }
+
+    #[test]
+    fn render_counters() {
+        let metrics = Metrics::default();
+        metrics.delivery_received("site/deploy", "github");
+        metrics.delivery_received("site/deploy", "github");
+        metrics.delivery_received("say \"hi\"\\", "any");
+        metrics.delivery_rejected(RejectionReason::BadSignature);
+        metrics.config_loaded(true);
+        metrics.config_loaded(false);
+        metrics.config_loaded(true);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(concat!(
+            "# HELP maidono_deliveries_received_total Deliveries received, by action and origin.\n",
+            "# TYPE maidono_deliveries_received_total counter\n",
+        )));
+        assert_eq!(
+            lines_of(&rendered, "maidono_deliveries_received_total"),
+            [
+                r#"maidono_deliveries_received_total{action="say \"hi\"\\",origin="any"} 1"#,
+                r#"maidono_deliveries_received_total{action="site/deploy",origin="github"} 2"#,
+            ]
+        );
+        assert_eq!(
+            lines_of(&rendered, "maidono_deliveries_rejected_total"),
+            [r#"maidono_deliveries_rejected_total{reason="bad_signature"} 1"#]
+        );
+        assert_eq!(
+            lines_of(&rendered, "maidono_config_loads_total"),
+            [
+                r#"maidono_config_loads_total{result="failure"} 1"#,
+                r#"maidono_config_loads_total{result="success"} 2"#,
+            ]
+        );
+        // the counters without any sample are still described
+        assert!(rendered.contains("# TYPE maidono_runs_cancelled_total counter\n"));
+        assert!(lines_of(&rendered, "maidono_runs_cancelled_total").is_empty());
+    }
+
+    #[test]
+    fn render_runs() {
+        let metrics = Metrics::default();
+        metrics.run_started("site/deploy");
+        metrics.run_started("site/deploy");
+        metrics.run_started("site/deploy");
+        metrics.run_finished(
+            "site/deploy",
+            RunStatus::Succeeded,
+            Duration::from_millis(250),
+        );
+        metrics.run_finished("site/deploy", RunStatus::TimedOut, Duration::from_secs(2));
+        assert_eq!(metrics.runs_in_progress(), 1);
+
+        let rendered = metrics.render();
+        assert_eq!(
+            lines_of(&rendered, "maidono_runs_started_total"),
+            [r#"maidono_runs_started_total{action="site/deploy"} 3"#]
+        );
+        assert_eq!(
+            lines_of(&rendered, "maidono_runs_succeeded_total"),
+            [r#"maidono_runs_succeeded_total{action="site/deploy"} 1"#]
+        );
+        assert_eq!(
+            lines_of(&rendered, "maidono_runs_failed_total"),
+            [r#"maidono_runs_failed_total{action="site/deploy"} 1"#]
+        );
+        assert_eq!(
+            lines_of(&rendered, "maidono_runs_in_progress"),
+            ["maidono_runs_in_progress 1"]
+        );
+
+        let histogram = lines_of(&rendered, "maidono_run_duration_seconds");
+        assert_eq!(histogram.len(), DURATION_BUCKETS.len() + 3);
+        assert_eq!(
+            histogram[..4],
+            [
+                r#"maidono_run_duration_seconds_bucket{action="site/deploy",le="0.1"} 0"#,
+                r#"maidono_run_duration_seconds_bucket{action="site/deploy",le="0.5"} 1"#,
+                r#"maidono_run_duration_seconds_bucket{action="site/deploy",le="1"} 1"#,
+                r#"maidono_run_duration_seconds_bucket{action="site/deploy",le="5"} 2"#,
+            ]
+        );
+        assert_eq!(
+            histogram[DURATION_BUCKETS.len()..],
+            [
+                r#"maidono_run_duration_seconds_bucket{action="site/deploy",le="+Inf"} 2"#,
+                r#"maidono_run_duration_seconds_sum{action="site/deploy"} 2.25"#,
+                r#"maidono_run_duration_seconds_count{action="site/deploy"} 2"#,
+            ]
+        );
+    }
+}
@@ -1,3 +1,7 @@
 mod actions;
+mod metrics;
+mod runs;
 
-pub use actions::{load_initial_actions, ActionRef, Actions};
+pub use actions::{load_actions, ActionRef, Actions, LoadedActions};
+pub use metrics::{Metrics, RejectionReason};
+pub use runs::{RunLog, RunRecord, Runs};
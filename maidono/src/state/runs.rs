@@ -0,0 +1,458 @@
+use crate::execution::{Context, RunReport, RunStatus};
+use crate::state::Metrics;
+use chrono::{DateTime, SecondsFormat, Utc};
+use maidono_core::control::{LogEntry, RunEvent};
+use maidono_core::utils::ActionPath;
+use rocket::serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+/// How many runs are remembered before the oldest ones are forgotten.
+const KEPT_RUNS: usize = 200;
+
+/// How much output is kept in the log of a run, in bytes.
+const KEPT_LOG_OUTPUT: usize = 256 * 1024;
+
+/// The longest line of output kept in the log of a run, in bytes. Longer lines are cut.
+const KEPT_LOG_LINE: usize = 4 * 1024;
+
+/// The runs started by the server, shared between the handlers and the running actions.
+#[derive(Clone, Default)]
+pub struct Runs {
+    history: Arc<Mutex<RunHistory>>,
+}
+
+#[derive(Default)]
+struct RunHistory {
+    records: HashMap<String, RunRecord>,
+    order: VecDeque<String>,
+    in_progress: HashMap<String, AbortHandle>,
+    logs: HashMap<String, Log>,
+}
+
+#[derive(Default)]
+struct Log {
+    entries: Vec<LogEntry>,
+    output_size: usize,
+    truncated: bool,
+}
+
+/// Where a run writes what happens while it runs, for the clients following it.
+#[derive(Clone)]
+pub struct RunLog {
+    runs: Runs,
+    id: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RunRecord {
+    id: String,
+    action: String,
+    chain: Vec<String>,
+    started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery: Option<String>,
+    #[serde(flatten)]
+    report: RunReport,
+    #[serde(skip)]
+    output_shared: bool,
+}
+
+impl Runs {
+    /// Registers a new run of the actions in a context, triggered by the action at `path`.
+    pub fn start(&self, path: &ActionPath, context: &Context) -> RunRecord {
+        let record = RunRecord {
+            id: Uuid::new_v4().to_string(),
+            action: path.to_string(),
+            chain: context
+                .actions()
+                .iter()
+                .map(|action| action.path().to_string())
+                .collect(),
+            started_at: Utc::now(),
+            finished_at: None,
+            delivery: context.delivery().map(str::to_owned),
+            report: RunReport::unfinished(RunStatus::Running),
+            output_shared: context.shares_output(),
+        };
+
+        let mut history = self.lock();
+        if history.order.len() >= KEPT_RUNS {
+            if let Some(oldest) = history.order.pop_front() {
+                history.records.remove(&oldest);
+                history.logs.remove(&oldest);
+            }
+        }
+        history.order.push_back(record.id.clone());
+        history.records.insert(record.id.clone(), record.clone());
+        history.logs.insert(record.id.clone(), Log::default());
+        record
+    }
+
+    pub fn log_of(&self, id: &str) -> RunLog {
+        RunLog {
+            runs: self.clone(),
+            id: id.to_owned(),
+        }
+    }
+
+    /// Keeps the handle of the task running the actions, so that the run can be cancelled.
+    pub fn attach(&self, id: &str, handle: AbortHandle) {
+        let mut history = self.lock();
+        // the run may already be over
+        if history
+            .records
+            .get(id)
+            .is_some_and(|record| record.status() == RunStatus::Running)
+        {
+            history.in_progress.insert(id.to_owned(), handle);
+        }
+    }
+
+    /// Records the result of a run, and returns the finished run. A run cancelled while it was
+    /// finishing stays cancelled, and is only counted once in the metrics.
+    pub fn finish(&self, record: RunRecord, report: RunReport, metrics: &Metrics) -> RunRecord {
+        let finished = RunRecord {
+            finished_at: Some(Utc::now()),
+            report,
+            ..record
+        };
+        let mut history = self.lock();
+        history.in_progress.remove(&finished.id);
+        if let Some(remembered) = history.records.get_mut(&finished.id) {
+            if remembered.finished_at.is_some() {
+                return remembered.clone();
+            }
+            *remembered = finished.clone();
+        }
+        // the metrics are updated under the lock, so that a cancellation can't count the run too
+        metrics.run_finished(
+            finished.action(),
+            finished.status(),
+            finished.duration().unwrap_or_default(),
+        );
+        history.record_event(&finished.id, finished_event(&finished));
+        finished
+    }
+
+    /// Stops a run in progress, killing the command it was running. Returns the cancelled run, or
+    /// nothing if there is no such run in progress.
+    pub fn cancel(&self, id: &str, metrics: &Metrics) -> Option<RunRecord> {
+        let mut history = self.lock();
+        let handle = history.in_progress.remove(id)?;
+        handle.abort();
+
+        let record = history.records.get_mut(id)?;
+        record.finished_at = Some(Utc::now());
+        record.report = RunReport::unfinished(RunStatus::Cancelled);
+        let record = record.clone();
+        metrics.run_finished(
+            record.action(),
+            record.status(),
+            record.duration().unwrap_or_default(),
+        );
+        history.record_event(id, finished_event(&record));
+        Some(record)
+    }
+
+    /// The runs that are not finished yet, starting with the most recent one.
+    pub fn in_progress(&self) -> Vec<RunRecord> {
+        self.list()
+            .into_iter()
+            .filter(|record| record.status() == RunStatus::Running)
+            .collect()
+    }
+
+    /// The remembered runs, starting with the most recent one.
+    pub fn list(&self) -> Vec<RunRecord> {
+        let history = self.lock();
+        history
+            .order
+            .iter()
+            .rev()
+            .filter_map(|id| history.records.get(id).cloned())
+            .collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<RunRecord> {
+        self.lock().records.get(id).cloned()
+    }
+
+    /// A run and the entries of its log, starting at the entry `from`.
+    pub fn log(&self, id: &str, from: usize) -> Option<(RunRecord, Vec<LogEntry>)> {
+        let history = self.lock();
+        let record = history.records.get(id)?.clone();
+        let entries = history
+            .logs
+            .get(id)
+            .map(|log| log.entries.iter().skip(from).cloned().collect())
+            .unwrap_or_default();
+        Some((record, entries))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RunHistory> {
+        // the history is always left in a consistent state, even if a thread panicked
+        self.history
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl RunHistory {
+    fn record_event(&mut self, id: &str, mut event: RunEvent) {
+        let Some(log) = self.logs.get_mut(id) else {
+            return;
+        };
+        if let RunEvent::Output { line, .. } = &mut event {
+            if log.truncated {
+                return;
+            }
+            truncate_line(line, KEPT_LOG_LINE);
+            log.output_size += line.len();
+            if log.output_size > KEPT_LOG_OUTPUT {
+                // the log tells once that the rest of the output is missing
+                log.truncated = true;
+                event = RunEvent::OutputTruncated;
+            }
+        }
+        log.entries.push(LogEntry {
+            time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            event,
+        });
+    }
+}
+
+impl RunLog {
+    pub fn record(&self, event: RunEvent) {
+        self.runs.lock().record_event(&self.id, event);
+    }
+}
+
+/// Cuts a line to at most `max` bytes, without splitting a character.
+fn truncate_line(line: &mut String, max: usize) {
+    if line.len() > max {
+        let mut end = max;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        line.truncate(end);
+    }
+}
+
+fn finished_event(record: &RunRecord) -> RunEvent {
+    RunEvent::Finished {
+        status: record.status().name().to_owned(),
+    }
+}
+
+impl RunRecord {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn chain(&self) -> &[String] {
+        &self.chain
+    }
+
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub fn finished_at(&self) -> Option<DateTime<Utc>> {
+        self.finished_at
+    }
+
+    pub fn delivery(&self) -> Option<&str> {
+        self.delivery.as_deref()
+    }
+
+    pub fn report(&self) -> &RunReport {
+        &self.report
+    }
+
+    pub fn status(&self) -> RunStatus {
+        self.report.status()
+    }
+
+    /// How long the run took, if it is finished.
+    pub fn duration(&self) -> Option<Duration> {
+        (self.finished_at? - self.started_at).to_std().ok()
+    }
+
+    /// The run as it can be shown to anyone, without the output of the commands unless the
+    /// action shares it.
+    pub fn public(self) -> Self {
+        if self.output_shared {
+            self
+        } else {
+            Self {
+                report: self.report.without_output(),
+                ..self
+            }
+        }
+    }
+
+    /// The same run, with a report that was not recorded in the history.
+    pub fn with_report(self, report: RunReport) -> Self {
+        Self { report, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maidono_core::actions::TriggerParameters;
+    use maidono_core::control::OutputStream;
+
+    fn start(runs: &Runs) -> RunRecord {
+        let context = Context::new(Vec::new(), TriggerParameters::new());
+        runs.start(&ActionPath::from_parts("g", "a"), &context)
+    }
+
+    fn finished_events(runs: &Runs, id: &str) -> Vec<String> {
+        let (_, entries) = runs.log(id, 0).unwrap();
+        entries
+            .into_iter()
+            .filter_map(|entry| match entry.event {
+                RunEvent::Finished { status } => Some(status),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn oldest_runs_are_forgotten() {
+        let runs = Runs::default();
+        let first = start(&runs);
+        let second = start(&runs);
+        for _ in 2..KEPT_RUNS {
+            start(&runs);
+        }
+        assert_eq!(runs.list().len(), KEPT_RUNS);
+        assert!(runs.get(first.id()).is_some());
+
+        let last = start(&runs);
+        let list = runs.list();
+        assert_eq!(list.len(), KEPT_RUNS);
+        assert_eq!(list[0].id(), last.id());
+        assert_eq!(list[KEPT_RUNS - 1].id(), second.id());
+        assert!(runs.get(first.id()).is_none());
+        assert!(runs.log(first.id(), 0).is_none());
+
+        // the result of a forgotten run is not remembered again
+        let finished = runs.finish(first, RunReport::new(), &Metrics::default());
+        assert_eq!(finished.status(), RunStatus::Succeeded);
+        assert!(runs.get(finished.id()).is_none());
+    }
+
+    #[tokio::test]
+    async fn cancel_then_finish() {
+        let runs = Runs::default();
+        let metrics = Metrics::default();
+        let other = start(&runs);
+        metrics.run_started(other.action());
+        let record = start(&runs);
+        metrics.run_started(record.action());
+        let task = tokio::spawn(std::future::pending::<()>());
+        runs.attach(record.id(), task.abort_handle());
+
+        let cancelled = runs.cancel(record.id(), &metrics).unwrap();
+        assert_eq!(cancelled.status(), RunStatus::Cancelled);
+        assert!(task.await.unwrap_err().is_cancelled());
+
+        // the task may have been finishing when it was cancelled
+        let finished = runs.finish(record.clone(), RunReport::new(), &metrics);
+        assert_eq!(finished.status(), RunStatus::Cancelled);
+        assert_eq!(metrics.runs_in_progress(), 1);
+        let rendered = metrics.render();
+        assert!(rendered.contains("maidono_runs_cancelled_total{action=\"g/a\"} 1\n"));
+        assert!(!rendered.contains("maidono_runs_succeeded_total{"));
+        assert_eq!(
+            runs.get(record.id()).unwrap().status(),
+            RunStatus::Cancelled
+        );
+        assert_eq!(finished_events(&runs, record.id()), ["cancelled"]);
+        assert_eq!(runs.in_progress()[0].id(), other.id());
+        assert_eq!(runs.in_progress().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn finish_then_cancel() {
+        let runs = Runs::default();
+        let record = start(&runs);
+        let task = tokio::spawn(std::future::pending::<()>());
+        runs.attach(record.id(), task.abort_handle());
+
+        let metrics = Metrics::default();
+        runs.finish(record.clone(), RunReport::new(), &metrics);
+        assert!(runs.cancel(record.id(), &metrics).is_none());
+        assert!(!metrics.render().contains("maidono_runs_cancelled_total{"));
+        assert_eq!(
+            runs.get(record.id()).unwrap().status(),
+            RunStatus::Succeeded
+        );
+        assert_eq!(finished_events(&runs, record.id()), ["succeeded"]);
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn attach_after_finish() {
+        let runs = Runs::default();
+        let record = start(&runs);
+        // the run may finish before its handle is kept
+        let metrics = Metrics::default();
+        runs.finish(record.clone(), RunReport::new(), &metrics);
+        let task = tokio::spawn(std::future::pending::<()>());
+        runs.attach(record.id(), task.abort_handle());
+
+        assert!(runs.cancel(record.id(), &metrics).is_none());
+        assert!(!task.is_finished());
+        assert_eq!(
+            runs.get(record.id()).unwrap().status(),
+            RunStatus::Succeeded
+        );
+        task.abort();
+    }
+
+    #[test]
+    fn log_output_is_capped() {
+        let runs = Runs::default();
+        let record = start(&runs);
+        let log = runs.log_of(record.id());
+        // the line is cut before the last complete character, at 4095 bytes
+        let line = format!("a{}", "é".repeat(KEPT_LOG_LINE));
+        for _ in 0..100 {
+            log.record(RunEvent::Output {
+                stream: OutputStream::Stdout,
+                line: line.clone(),
+            });
+        }
+
+        let (_, entries) = runs.log(record.id(), 0).unwrap();
+        let lines: Vec<usize> = entries
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                RunEvent::Output { line, .. } => Some(line.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![KEPT_LOG_LINE - 1; KEPT_LOG_OUTPUT / (KEPT_LOG_LINE - 1)]
+        );
+        assert!(matches!(
+            entries.last().unwrap().event,
+            RunEvent::OutputTruncated
+        ));
+    }
+}
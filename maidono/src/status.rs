@@ -0,0 +1,111 @@
+use crate::access::TokenCheck;
+use crate::state::{LoadedActions, Metrics, RunRecord, Runs};
+use chrono::{DateTime, Utc};
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::{get, routes, Request, Route, State};
+
+pub(crate) fn routes() -> Vec<Route> {
+    routes![run_status, healthz, readyz]
+}
+
+pub(crate) fn metrics_routes() -> Vec<Route> {
+    routes![metrics]
+}
+
+#[get("/runs/<id>")]
+fn run_status(id: &str, runs: &State<Runs>) -> Option<Json<RunRecord>> {
+    runs.get(id).map(RunRecord::public).map(Json)
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct Health {
+    status: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct Readiness {
+    ready: bool,
+    last_load_succeeded: bool,
+    loaded_at: DateTime<Utc>,
+    enabled_actions: usize,
+    runs_in_progress: u64,
+    saturated: bool,
+}
+
+/// Answers as long as the server is running.
+#[get("/healthz")]
+fn healthz() -> Json<Health> {
+    Json(Health { status: "ok" })
+}
+
+/// Tells whether the server can handle webhooks, which is when there are less runs in progress
+/// than the `max_runs_in_progress` setting (if there is one). The server doesn't launch without
+/// actions, and keeps the previous ones when a reload fails, so they are always loaded.
+#[get("/readyz")]
+fn readyz(
+    loaded: &State<LoadedActions>,
+    metrics: &State<Metrics>,
+    max_runs: MaxRunsInProgress,
+) -> (Status, Json<Readiness>) {
+    let actions = loaded.get();
+    let runs_in_progress = metrics.runs_in_progress();
+    let saturated = max_runs.0.is_some_and(|max| runs_in_progress >= max);
+
+    let readiness = Readiness {
+        ready: !saturated,
+        last_load_succeeded: loaded.last_load_succeeded(),
+        loaded_at: actions.loaded_at(),
+        enabled_actions: actions.enabled_count(),
+        runs_in_progress,
+        saturated,
+    };
+    let status = if readiness.ready {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (status, Json(readiness))
+}
+
+#[get("/metrics")]
+fn metrics(_access: MetricsAccess, metrics: &State<Metrics>) -> (ContentType, String) {
+    let content_type = ContentType::new("text", "plain").with_params(("version", "0.0.4"));
+    (content_type, metrics.render())
+}
+
+/// Checks the bearer token of the request if a `metrics_token` is configured.
+struct MetricsAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MetricsAccess {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match TokenCheck::of(req, "metrics_token") {
+            TokenCheck::Valid | TokenCheck::Unset => Outcome::Success(MetricsAccess),
+            TokenCheck::Invalid => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// The `max_runs_in_progress` setting.
+struct MaxRunsInProgress(Option<u64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MaxRunsInProgress {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let max = req
+            .rocket()
+            .figment()
+            .extract_inner("max_runs_in_progress")
+            .ok();
+        Outcome::Success(MaxRunsInProgress(max))
+    }
+}
@@ -0,0 +1,54 @@
+const TOKEN_KEY = 'maidono-token'
+
+export class ApiError extends Error {
+  constructor(status, message) {
+    super(message)
+    this.status = status
+  }
+}
+
+export function getToken() {
+  return sessionStorage.getItem(TOKEN_KEY)
+}
+
+export function setToken(token) {
+  if (token) {
+    sessionStorage.setItem(TOKEN_KEY, token)
+  } else {
+    sessionStorage.removeItem(TOKEN_KEY)
+  }
+}
+
+/**
+ * Calls the admin API of the server with the token of the session.
+ */
+export async function api(method, path, body = undefined, token = getToken()) {
+  const headers = { Authorization: `Bearer ${token}` }
+  if (body !== undefined) {
+    headers['Content-Type'] = 'application/json'
+  }
+
+  const response = await fetch(`/api${path}`, {
+    method,
+    headers,
+    body: body === undefined ? undefined : JSON.stringify(body),
+  })
+
+  if (!response.ok) {
+    let message = `${response.status} ${response.statusText}`
+    try {
+      message = (await response.json()).error ?? message
+    } catch {
+      // the default catchers of the server don't respond with JSON
+    }
+    throw new ApiError(response.status, message)
+  }
+  return response.json()
+}
+
+/**
+ * The names of the parameters of a trigger, like `env` in `POST /deploy/<env>`.
+ */
+export function triggerParameters(trigger) {
+  return [...trigger.matchAll(/<([^>]+)>/g)].map((match) => match[1])
+}
@@ -4,7 +4,25 @@ import '/src/assets/app.css'
 
 import { library } from '@fortawesome/fontawesome-svg-core'
 import { FontAwesomeIcon } from '@fortawesome/vue-fontawesome'
-import { faRocket } from '@fortawesome/free-solid-svg-icons'
-library.add(faRocket)
+import {
+  faChevronDown,
+  faChevronUp,
+  faPlay,
+  faRightFromBracket,
+  faRightToBracket,
+  faRocket,
+  faRotate,
+  faTriangleExclamation,
+} from '@fortawesome/free-solid-svg-icons'
+library.add(
+  faChevronDown,
+  faChevronUp,
+  faPlay,
+  faRightFromBracket,
+  faRightToBracket,
+  faRocket,
+  faRotate,
+  faTriangleExclamation,
+)
 
 createApp(App).component('fa', FontAwesomeIcon).mount('#app')